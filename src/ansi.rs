@@ -0,0 +1,97 @@
+use crate::{
+	screen::{Cell, Screen},
+	styles::StyleNode,
+};
+
+/// The output as a terminal would display it, written back out as text with the fewest escape sequences.
+/// Styles only change where they look different, hyperlinks are kept and cursor movement, titles and
+/// everything else a terminal doesn't show are dropped.
+pub struct AnsiRenderer;
+
+impl AnsiRenderer {
+	pub fn render_screen(screen: &Screen) -> String {
+		let mut ansi = String::new();
+
+		for (row, line) in screen.lines().enumerate() {
+			if row > 0 {
+				ansi.push('\n');
+			}
+			Self::push_line(&mut ansi, screen, line);
+		}
+
+		ansi
+	}
+
+	// Every line ends with the default style and no link so lines can be read or cut on their own
+	fn push_line(ansi: &mut String, screen: &Screen, line: &[Option<Cell>]) {
+		let mut style = StyleNode::default();
+		let mut link = None;
+
+		for cell in line {
+			let cell = cell.unwrap_or(Cell {
+				ch: ' ',
+				style: StyleNode::default(),
+				link: None,
+			});
+
+			if cell.link != link {
+				if link.is_some() {
+					ansi.push_str("\x1B]8;;\x1B\\");
+				}
+				if let Some(hyperlink) = cell.link.and_then(|index| screen.links().get(index)) {
+					let params = hyperlink.id.as_ref().map(|id| format!("id={id}")).unwrap_or_default();
+					ansi.push_str(&format!("\x1B]8;{params};{}\x1B\\", hyperlink.url));
+				}
+				link = cell.link;
+			}
+			ansi.push_str(&style.transition_to(&cell.style));
+			ansi.push(cell.ch);
+		}
+
+		if link.is_some() {
+			ansi.push_str("\x1B]8;;\x1B\\");
+		}
+		if !style.is_default() {
+			ansi.push_str("\x1B[0m");
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8]) -> String {
+		AnsiRenderer::render_screen(&Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input)))
+	}
+
+	#[test]
+	fn minify_test() {
+		assert_eq!(render(b"plain\n"), "plain\n");
+		assert_eq!(
+			render(b"\x1B[0m\x1B[0m\x1B[31m\x1B[31mred\x1B[0m\x1B[0m \x1B[m\x1B[1;4m\x1B[22m\x1B[24mplain\x1B[0m\n"),
+			"\x1B[31mred\x1B[0m plain\n"
+		);
+		// Only what changes is written and the style is reset at the end of every line
+		assert_eq!(
+			render(b"\x1B[1;32mok\x1B[3m!\x1B[22;23m ok\x1B[1;3;4;9;44m\n"),
+			"\x1B[1;32mok\x1B[3m!\x1B[0;32m ok\x1B[0m\n"
+		);
+		assert_eq!(render(b"\x1B[1;31ma\x1B[0;32mb\x1B[0m"), "\x1B[1;31ma\x1B[0;32mb\x1B[0m");
+	}
+
+	#[test]
+	fn cursor_test() {
+		assert_eq!(render(b"\x1B[?25l10%\r50%\r\x1B[K\x1B[32mdone\x1B[0m\x1B[?25h\n"), "\x1B[32mdone\x1B[0m\n");
+		assert_eq!(render(b"\x1B]0;title\x07a\x1B[2Cb\x1B[1A"), "a  b");
+	}
+
+	#[test]
+	fn links_test() {
+		assert_eq!(
+			render(b"see \x1B]8;id=1;https://example.com\x1B\\\x1B[4mdocs\x1B]8;;\x1B\\\x1B[24m.\n\x1B]8;;https://a.example\x07a\nb"),
+			"see \x1B]8;id=1;https://example.com\x1B\\\x1B[4mdocs\x1B]8;;\x1B\\\x1B[0m.\n\x1B]8;;https://a.example\x1B\\a\x1B]8;;\x1B\\\n\x1B]8;;https://a.example\x1B\\b\x1B]8;;\x1B\\"
+		);
+	}
+}
@@ -0,0 +1,344 @@
+use crate::{
+	json::Json,
+	nodes::TerminalOutputStream,
+	palette::{Palette, Rgb},
+	screen::Screen,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsciicastError {
+	pub line: usize,
+	pub message: String,
+}
+
+impl std::fmt::Display for AsciicastError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "Invalid asciicast on line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for AsciicastError {}
+
+/// An asciinema recording in the asciicast v2 format
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asciicast {
+	pub width: usize,
+	pub height: usize,
+	pub title: Option<String>,
+	/// The colors of the recording terminal when the header has a theme
+	pub palette: Option<Palette>,
+	/// Output events as seconds since the start of the recording and the data written to the terminal
+	pub events: Vec<(f64, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+	/// Seconds since the start of the animation
+	pub time: f64,
+	/// What was in view at that time, without any scrollback
+	pub screen: Screen,
+}
+
+#[derive(Debug, Clone)]
+pub struct Animation {
+	pub width: usize,
+	pub height: usize,
+	/// The title from the recording's header
+	pub title: Option<String>,
+	pub frames: Vec<Frame>,
+	/// Seconds until the animation starts over, which holds the last frame for a moment
+	pub duration: f64,
+}
+
+impl Asciicast {
+	/// Output closer together than this ends up in the same frame
+	pub const FRAME_INTERVAL: f64 = 1.0 / 30.0;
+	/// How long the last frame stays before the animation loops
+	pub const END_PAUSE: f64 = 2.0;
+
+	pub fn parse(input: &str) -> Result<Self, AsciicastError> {
+		let mut lines =
+			input.lines().enumerate().map(|(index, line)| (index + 1, line)).filter(|(_, line)| !line.trim().is_empty());
+		let error = |line: usize, message: String| AsciicastError { line, message };
+
+		let Some((line, header)) = lines.next() else {
+			return Err(error(1, String::from("missing header")));
+		};
+		let header = Json::parse(header).map_err(|json| error(line, json.message))?;
+
+		match header.get("version") {
+			Some(Json::Number(version)) if *version == 2.0 => {},
+			Some(Json::Number(version)) => return Err(error(line, format!("unsupported version {version}, expected 2"))),
+			_ => return Err(error(line, String::from("missing version in header"))),
+		}
+
+		// Every frame holds a full screen so sizes are limited to what a screen without a size could grow to
+		let size = |name: &str, max: usize| match header.get(name) {
+			Some(Json::Number(size)) if *size > max as f64 => {
+				Err(error(line, format!("{name} of {size} is larger than the maximum of {max}")))
+			},
+			Some(Json::Number(size)) if *size >= 1.0 => Ok(*size as usize),
+			_ => Err(error(line, format!("missing or invalid {name} in header"))),
+		};
+		let width = size("width", Screen::MAX_COLUMNS)?;
+		let height = size("height", Screen::MAX_ROWS)?;
+
+		let title = match header.get("title") {
+			Some(Json::String(title)) => Some(title.clone()),
+			_ => None,
+		};
+		let idle_time_limit = match header.get("idle_time_limit") {
+			Some(Json::Number(limit)) if *limit > 0.0 => Some(*limit),
+			_ => None,
+		};
+		let palette = match header.get("theme") {
+			Some(theme) => Some(Self::parse_theme(theme).map_err(|message| error(line, message))?),
+			None => None,
+		};
+
+		let mut events = Vec::new();
+		let mut recorded = 0.0;
+		let mut time = 0.0;
+
+		for (line, event) in lines {
+			let Ok(Json::Array(event)) = Json::parse(event) else {
+				return Err(error(line, String::from("expected an event like [time, \"o\", data]")));
+			};
+			let [Json::Number(event_time), Json::String(kind), Json::String(data)] = event.as_slice() else {
+				return Err(error(line, String::from("expected an event like [time, \"o\", data]")));
+			};
+
+			// Pauses longer than the idle time limit are cut down to it
+			let gap = (event_time - recorded).max(0.0);
+			recorded = event_time.max(recorded);
+			time += idle_time_limit.map_or(gap, |limit| gap.min(limit));
+
+			// Input, marker and resize events don't change what is on screen
+			if kind == "o" {
+				events.push((time, data.clone()));
+			}
+		}
+
+		Ok(Self {
+			width,
+			height,
+			title,
+			palette,
+			events,
+		})
+	}
+
+	// "theme": { "fg": "#d0d0d0", "bg": "#212121", "palette": "#151515:#ac4142:..." } with 8 or 16 colors
+	fn parse_theme(theme: &Json) -> Result<Palette, String> {
+		let color = |name: &str| match theme.get(name) {
+			Some(Json::String(spec)) => {
+				Rgb::parse_x11(spec).map(Some).ok_or_else(|| format!("invalid color \"{spec}\" in theme"))
+			},
+			None => Ok(None),
+			Some(_) => Err(format!("invalid {name} in theme")),
+		};
+
+		let mut ansi = Palette::XTERM;
+		if let Some(Json::String(colors)) = theme.get("palette") {
+			let colors = colors
+				.split(':')
+				.map(|spec| Rgb::parse_x11(spec).ok_or_else(|| format!("invalid color \"{spec}\" in theme")))
+				.collect::<Result<Vec<_>, _>>()?;
+			if colors.len() != 8 && colors.len() != 16 {
+				return Err(format!("expected 8 or 16 colors in the theme palette but found {}", colors.len()));
+			}
+			ansi[..colors.len()].copy_from_slice(&colors);
+			if colors.len() == 8 {
+				ansi.copy_within(..8, 8);
+			}
+		}
+
+		let mut palette = Palette::from_ansi(&ansi);
+		palette.foreground = color("fg")?;
+		palette.background = color("bg")?;
+		Ok(palette)
+	}
+
+	/// Replays the recording on a screen of the recorded size and keeps a frame for every change
+	pub fn animation(&self, palette: Palette) -> Animation {
+		let mut screen = Screen::new(Some(self.width), Some(self.height)).with_palette(palette).without_scrollback();
+		let mut stream = TerminalOutputStream::default();
+		let mut frames = vec![Frame {
+			time: 0.0,
+			screen: screen.snapshot(),
+		}];
+
+		for (time, data) in &self.events {
+			screen.apply_all(&stream.push(data.as_bytes()));
+
+			match frames.last_mut() {
+				Some(frame) if time - frame.time < Self::FRAME_INTERVAL => frame.screen = screen.snapshot(),
+				_ => frames.push(Frame {
+					time: *time,
+					screen: screen.snapshot(),
+				}),
+			}
+		}
+
+		screen.apply_all(&stream.finish());
+		if let Some(frame) = frames.last_mut() {
+			frame.screen = screen.snapshot();
+		}
+
+		let duration = frames.last().map_or(0.0, |frame| frame.time) + Self::END_PAUSE;
+		Animation {
+			width: self.width,
+			height: self.height,
+			title: self.title.clone(),
+			frames,
+			duration,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn frame_text(frame: &Frame) -> Vec<String> {
+		frame
+			.screen
+			.viewport()
+			.iter()
+			.map(|line| line.iter().map(|cell| cell.map_or(' ', |cell| cell.ch)).collect())
+			.collect()
+	}
+
+	#[test]
+	fn parse_test() {
+		let cast = Asciicast::parse(
+			r##"{"version": 2, "width": 20, "height": 2, "title": "demo", "idle_time_limit": 1.5, "env": {"TERM": "xterm-256color"}}
+[0.5, "o", "$ ls\r\n"]
+[0.6, "i", "q"]
+[1.0, "m", ""]
+
+[5.0, "o", "\u001b[31mdone\u001b[0m \ud83e\udd80"]
+"##,
+		)
+		.unwrap();
+
+		assert_eq!(cast.width, 20);
+		assert_eq!(cast.height, 2);
+		assert_eq!(cast.title, Some(String::from("demo")));
+		assert_eq!(cast.palette, None);
+		// The four second pause is cut down to the idle time limit
+		assert_eq!(
+			cast.events,
+			vec![
+				(0.5, String::from("$ ls\r\n")),
+				(2.5, String::from("\x1B[31mdone\x1B[0m \u{1F980}"))
+			]
+		);
+	}
+
+	#[test]
+	fn parse_theme_test() {
+		let cast = Asciicast::parse(
+			r##"{"version": 2, "width": 2, "height": 1, "theme": {"fg": "#eeeeee", "bg": "#111111", "palette": "#000000:#ff0000:#00ff00:#ffff00:#0000ff:#ff00ff:#00ffff:#ffffff"}}"##,
+		)
+		.unwrap();
+		let palette = cast.palette.unwrap();
+
+		assert_eq!(palette.get(1), Rgb::new(0xff, 0, 0));
+		assert_eq!(palette.get(9), Rgb::new(0xff, 0, 0));
+		assert_eq!(palette.foreground, Some(Rgb::new(0xee, 0xee, 0xee)));
+		assert_eq!(palette.background, Some(Rgb::new(0x11, 0x11, 0x11)));
+	}
+
+	#[test]
+	fn parse_errors_test() {
+		let error = |input: &str| Asciicast::parse(input).unwrap_err().to_string();
+
+		assert_eq!(error(""), "Invalid asciicast on line 1: missing header");
+		assert_eq!(
+			error(r#"{"version": 1, "width": 80, "height": 24}"#),
+			"Invalid asciicast on line 1: unsupported version 1, expected 2"
+		);
+		assert_eq!(
+			error(r#"{"version": 2, "width": 80}"#),
+			"Invalid asciicast on line 1: missing or invalid height in header"
+		);
+		assert_eq!(
+			error(r#"{"version":2,"width":5,"height":400000000}"#),
+			"Invalid asciicast on line 1: height of 400000000 is larger than the maximum of 1000"
+		);
+		assert_eq!(
+			error(r#"{"version":2,"width":1001,"height":24}"#),
+			"Invalid asciicast on line 1: width of 1001 is larger than the maximum of 1000"
+		);
+		assert_eq!(
+			error("{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.1, \"o\", \"a\"]\n[0.2, \"o\"]"),
+			"Invalid asciicast on line 3: expected an event like [time, \"o\", data]"
+		);
+		assert_eq!(
+			error("{\"version\": 2, \"width\": 80, \"height\": 24}\nnot json"),
+			"Invalid asciicast on line 2: expected an event like [time, \"o\", data]"
+		);
+		assert_eq!(
+			error(&format!("{{\"version\": 2, \"width\": 80, \"height\": 24}}\n{}", "[".repeat(200_000))),
+			"Invalid asciicast on line 2: expected an event like [time, \"o\", data]"
+		);
+		assert_eq!(
+			error(r##"{"version": 2, "width": 2, "height": 1, "theme": {"palette": "#000000:#ff0000"}}"##),
+			"Invalid asciicast on line 1: expected 8 or 16 colors in the theme palette but found 2"
+		);
+	}
+
+	#[test]
+	fn animation_test() {
+		let cast = Asciicast {
+			width: 5,
+			height: 2,
+			title: None,
+			palette: None,
+			events: vec![
+				(0.5, String::from("a\r")),
+				(0.51, String::from("\nb\x1B[3")),
+				(1.0, String::from("1mc\r\nd\r\ne")),
+			],
+		};
+		let animation = cast.animation(Palette::default());
+
+		assert_eq!(animation.frames.len(), 3);
+		assert_eq!(animation.duration, 3.0);
+		assert_eq!(animation.frames[0].time, 0.0);
+		assert_eq!(frame_text(&animation.frames[0]), vec!["", ""]);
+		// Output within a frame interval is merged and a CRLF split between events stays one newline
+		assert_eq!(animation.frames[1].time, 0.5);
+		assert_eq!(frame_text(&animation.frames[1]), vec!["a", "b"]);
+		// A sequence split between events still applies and the screen scrolls
+		assert_eq!(frame_text(&animation.frames[2]), vec!["d", "e"]);
+		assert_eq!(animation.frames[2].screen.lines().nth(1).unwrap()[0].unwrap().style, {
+			let mut style = crate::styles::StyleNode::default();
+			style.apply_sgr(&[vec![31]]);
+			style
+		});
+	}
+
+	#[test]
+	fn frame_size_test() {
+		let cast = Asciicast {
+			width: 10,
+			height: 3,
+			title: None,
+			palette: None,
+			events: (0..500)
+				.map(|i| (i as f64, format!("\x1B]8;;https://example.com/{i}\x1B\\line {i}\x1B]8;;\x1B\\\r\n")))
+				.collect(),
+		};
+		let animation = cast.animation(Palette::default());
+
+		assert_eq!(animation.frames.len(), 500);
+		// Frames only hold what is in view and the links used there no matter how much has scrolled by
+		for frame in &animation.frames {
+			assert!(frame.screen.lines().count() <= 3);
+			assert!(frame.screen.links().len() <= 3);
+		}
+		assert_eq!(frame_text(animation.frames.last().unwrap()), vec!["line 498", "line 499", ""]);
+	}
+}
@@ -0,0 +1,119 @@
+use crate::{json::Json, nodes::AnsiNode, screen::Screen, styles::StyleNode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum JsonContent {
+	/// The nodes straight from the parser
+	Nodes,
+	/// Lines of text runs with their resolved style from the screen the nodes were applied to
+	Runs,
+	#[default]
+	Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct JsonOptions {
+	pub content: JsonContent,
+}
+
+/// The parsed output as JSON for tools in other languages, with "nodes" and "lines" of runs like
+/// {"text":"Error","style":{"foreground":{"hex":"#cd0000","index":1},...},"link":null}.
+/// Styles in runs are resolved so reverse and hidden are always false, their colors are already swapped and hidden
+/// text is spaces.
+pub struct JsonRenderer;
+
+impl JsonRenderer {
+	/// Renders the nodes together with the screen they were applied to
+	pub fn render(nodes: &[AnsiNode], screen: &Screen, options: &JsonOptions) -> String {
+		let mut entries = Vec::new();
+
+		if options.content != JsonContent::Runs {
+			entries.push((String::from("nodes"), Json::Array(nodes.iter().map(AnsiNode::to_json).collect())));
+		}
+		if options.content != JsonContent::Nodes {
+			entries.push((String::from("lines"), Self::lines(screen)));
+		}
+
+		Json::Object(entries).to_string()
+	}
+
+	fn lines(screen: &Screen) -> Json {
+		let lines = screen.lines().map(|line| {
+			let mut runs: Vec<(StyleNode, Option<usize>, String)> = Vec::new();
+			for cell in line {
+				let (mut ch, mut style, link) =
+					cell.map_or((' ', StyleNode::default(), None), |cell| (cell.ch, cell.style, cell.link));
+				// Hidden text is resolved to spaces like reverse is resolved to colors
+				if style.hidden() {
+					ch = ' ';
+					style.apply_sgr(&[vec![28]]);
+				}
+				match runs.last_mut() {
+					Some((last, last_link, text)) if last.effective() == style.effective() && *last_link == link => text.push(ch),
+					_ => runs.push((style, link, ch.to_string())),
+				}
+			}
+
+			Json::Array(
+				runs
+					.into_iter()
+					.map(|(style, link, text)| {
+						let url = link.and_then(|link| screen.links().get(link)).map(|link| link.url.clone());
+						Json::Object(vec![
+							(String::from("text"), Json::String(text)),
+							(String::from("style"), style.to_json(screen.palette())),
+							(String::from("link"), url.map_or(Json::Null, Json::String)),
+						])
+					})
+					.collect(),
+			)
+		});
+
+		Json::Array(lines.collect())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8], content: JsonContent) -> String {
+		let nodes = TerminalOutputParser::parse_to_nodes(input);
+		JsonRenderer::render(&nodes, &Screen::from_nodes(&nodes), &JsonOptions { content })
+	}
+
+	#[test]
+	fn nodes_test() {
+		assert_eq!(
+			render(b"\x1B[1;38:5:208mhi\x1B(B\x07\x1B]0;title\x07", JsonContent::Nodes),
+			r#"{"nodes":[{"csi":{"params":[[1],[38,5,208]],"intermediates":[],"code":"m"}},{"text":"hi"},{"esc":{"intermediates":[40],"byte":66}},{"control":7},{"osc":{"params":[[48],[116,105,116,108,101]],"bell_terminated":true}}]}"#
+		);
+	}
+
+	#[test]
+	fn runs_test() {
+		let style = r#""underline":null,"underline_color":null,"font":null,"bold":false,"dim":false,"italic":false,"blink":false,"rapid_blink":false,"reverse":false,"hidden":false,"strikethrough":false,"superscript":false,"subscript":false,"overlined":false,"framed":false,"encircled":false,"fraktur":false,"proportional_spacing":false"#;
+
+		assert_eq!(
+			render(b"a\x1B[38;2;1;2;3mb\x1B[0m\n\x1B]8;;https://example.com\x1B\\c\x1B]8;;\x1B\\", JsonContent::Runs),
+			format!(
+				r##"{{"lines":[[{{"text":"a","style":{{"foreground":null,"background":null,{style}}},"link":null}},{{"text":"b","style":{{"foreground":{{"hex":"#010203","index":null}},"background":null,{style}}},"link":null}}],[{{"text":"c","style":{{"foreground":null,"background":null,{style}}},"link":"https://example.com"}}]]}}"##
+			)
+		);
+
+		// Reverse, bold upgrading to a bright color and the other attributes are resolved and reverse isn't left set
+		let json = render(b"\x1B[1;31;7;4:3;12mx", JsonContent::Both);
+		assert!(json.starts_with(r#"{"nodes":[{"csi":{"params":[[1],[31],[7],[4,3],[12]],"#));
+		assert!(json.ends_with(
+			r##""lines":[[{"text":"x","style":{"foreground":null,"background":{"hex":"#ff0000","index":9},"underline":"curly","underline_color":null,"font":"two","bold":true,"dim":false,"italic":false,"blink":false,"rapid_blink":false,"reverse":false,"hidden":false,"strikethrough":false,"superscript":false,"subscript":false,"overlined":false,"framed":false,"encircled":false,"fraktur":false,"proportional_spacing":false},"link":null}]]}"##
+		));
+
+		// Hidden text is blanked and joins the run of the spaces around it
+		assert_eq!(
+			render(b" \x1B[8mpw\x1B[28m ", JsonContent::Runs),
+			format!(
+				r#"{{"lines":[[{{"text":"    ","style":{{"foreground":null,"background":null,{style}}},"link":null}}]]}}"#
+			)
+		);
+	}
+}
@@ -0,0 +1,488 @@
+use std::path::PathBuf;
+
+use shellvetica::{
+	ast::{JsonContent, JsonOptions},
+	html::{CssVariables, HtmlOptions, StyleMode},
+	latex::{LatexEnvironment, LatexOptions},
+	links::{LinkPolicy, RejectedLink},
+	markdown::{MarkdownMode, MarkdownOptions},
+	palette::ColorDepth,
+	rtf::RtfOptions,
+	screen::{CarriageReturn, TitleSelection},
+	svg::SvgOptions,
+	text::TextOptions,
+	themes::Theme,
+};
+
+pub const HELP: &str = "\
+shellvetica - Bring your shell output to the web
+
+USAGE:
+  some-command | shellvetica [OPTIONS]
+
+OPTIONS:
+  --format <format>             Output format, one of html, svg, text, markdown, latex, rtf, json,
+                                ansi [default: html]
+  --theme <name>                Color theme, one of xterm, vga, solarized-dark, solarized-light, dracula, gruvbox,
+                                one-dark, nord, tango, terminal-app, campbell
+  --theme-file <path>           Load colors from an iTerm2 .itermcolors, Alacritty .toml/.yml, Windows Terminal
+                                .json, kitty .conf or Xresources file
+  --theme-scheme <name>         Which scheme to use from a Windows Terminal settings.json [default: the first one]
+  --colors <depth>              Reduce colors to the closest of a 256 color palette or the 16 theme colors, one of
+                                truecolor, 256, 16 [default: truecolor]
+  --document                    Output a full HTML document instead of a fragment
+  --font <stack>                CSS font stack of the document or image, RTF uses the first font
+                                [default: ui-monospace,SFMono-Regular,...]
+  --font-size <px>              Font size of the image [default: 14]
+  --window-chrome               Draw a window frame around the image
+  --title <first|last>          Which terminal title to use for the document [default: last]
+  --title-header                Show the terminal title as a heading above the output
+  --classes                     Use classes and a stylesheet instead of inline styles
+  --class-prefix <prefix>       Prefix of the classes [default: sv-]
+  --stylesheet <path>           Write the stylesheet of --classes to a file and link to it instead of embedding it
+  --email                       Use only inline styles, <font> tags and a table that email clients support
+  --css-variables               Use CSS custom properties like var(--sv-red) for the 16 ANSI and default colors with
+                                light and dark definitions picked by prefers-color-scheme
+  --light-theme <name>          Theme for the light CSS custom properties [default: solarized-light]
+  --dark-theme <name>           Theme for the dark CSS custom properties [default: solarized-dark]
+  --asciicast                   Read an asciicast v2 recording and output an HTML player or an animated SVG
+  --markdown-code               Put the whole output in one Markdown code block
+  --markdown-ansi               Use ```ansi code blocks that keep colors on GitHub and Discord for styled output
+  --latex-listings              Use a listings block instead of a fancyvrb Verbatim block
+  --preamble <path>             Write the LaTeX color definitions to a file instead of putting them before the block
+  --text-links                  Print the url of links after their text in the text format
+  --json <content>              What the json format contains, one of nodes, runs, both [default: both]
+  --keep-frames                 Keep every state of lines redrawn with a carriage return instead of only the last
+  --link-schemes <list>         Comma separated url schemes allowed in links [default: http,https,file,mailto]
+  --link-nofollow               Add rel=\"noopener nofollow\" to links
+  --link-target <target>        Add a target attribute to links, e.g. _blank
+  --file-base-url <url>         Rewrite file://host/path links to <url>/path
+  --show-rejected-links         Print the url of links that are not allowed next to their text instead of dropping it
+  -h, --help                    Print this help
+";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Format {
+	#[default]
+	Html,
+	Svg,
+	Text,
+	Markdown,
+	Latex,
+	Rtf,
+	Json,
+	Ansi,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Cli {
+	pub format: Format,
+	pub carriage_return: CarriageReturn,
+	pub theme: Option<Theme>,
+	pub theme_file: Option<PathBuf>,
+	pub theme_scheme: Option<String>,
+	pub color_depth: ColorDepth,
+	pub html: HtmlOptions,
+	pub svg: SvgOptions,
+	pub text: TextOptions,
+	pub markdown: MarkdownOptions,
+	pub latex: LatexOptions,
+	pub preamble: Option<PathBuf>,
+	pub rtf: RtfOptions,
+	pub json: JsonOptions,
+	pub stylesheet: Option<PathBuf>,
+	pub asciicast: bool,
+	pub help: bool,
+}
+
+impl Cli {
+	pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+		let mut cli = Self::default();
+		let mut args = args;
+
+		while let Some(arg) = args.next() {
+			// Both "--option value" and "--option=value" are supported
+			let (name, inline_value) = match arg.split_once('=') {
+				Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+				_ => (arg.clone(), None),
+			};
+			let mut value =
+				|| inline_value.clone().or_else(|| args.next()).ok_or_else(|| format!("Missing value for option \"{name}\""));
+			let link_policy = &mut cli.html.link_policy;
+
+			match name.as_str() {
+				"--format" => {
+					cli.format = match value()?.as_str() {
+						"html" => Format::Html,
+						"svg" => Format::Svg,
+						"text" => Format::Text,
+						"markdown" | "md" => Format::Markdown,
+						"latex" | "tex" => Format::Latex,
+						"rtf" => Format::Rtf,
+						"json" => Format::Json,
+						"ansi" => Format::Ansi,
+						other => {
+							return Err(format!(
+								"Unknown format \"{other}\", expected html, svg, text, markdown, latex, rtf, json or ansi"
+							));
+						},
+					}
+				},
+				"--theme" => cli.theme = Some(Self::theme(&value()?)?),
+				"--theme-file" => cli.theme_file = Some(PathBuf::from(value()?)),
+				"--theme-scheme" => cli.theme_scheme = Some(value()?),
+				"--colors" => {
+					cli.color_depth = match value()?.as_str() {
+						"truecolor" | "24bit" => ColorDepth::TrueColor,
+						"256" => ColorDepth::Palette256,
+						"16" => ColorDepth::Ansi16,
+						other => return Err(format!("Invalid colors \"{other}\", expected truecolor, 256 or 16")),
+					}
+				},
+				"--document" => cli.html.document = true,
+				"--font" => {
+					let font_family = value()?;
+					if font_family.contains(['<', '>', '{', '}', ';']) {
+						return Err(format!("Invalid font \"{font_family}\""));
+					}
+					// RTF takes a single font name without CSS quotes
+					let first = font_family.split(',').next().unwrap_or_default().trim().trim_matches(['\'', '"']);
+					if !first.is_empty() {
+						cli.rtf.font_family = first.to_string();
+					}
+					cli.html.font_family = font_family.clone();
+					cli.svg.font_family = font_family;
+				},
+				"--title" => {
+					cli.html.title = match value()?.as_str() {
+						"first" => TitleSelection::First,
+						"last" => TitleSelection::Last,
+						other => return Err(format!("Invalid title \"{other}\", expected first or last")),
+					};
+					cli.svg.title = cli.html.title;
+				},
+				"--title-header" => cli.html.title_header = true,
+				// Email clients strip the <style> that classes need
+				"--classes" | "--email" => {
+					let style_mode = if name == "--classes" {
+						StyleMode::Classes
+					} else {
+						StyleMode::Email
+					};
+					if cli.html.style_mode != StyleMode::default() && cli.html.style_mode != style_mode {
+						return Err(String::from("Options \"--classes\" and \"--email\" can't be used together"));
+					}
+					cli.html.style_mode = style_mode;
+				},
+				"--class-prefix" => {
+					let prefix = value()?;
+					if !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+						return Err(format!(
+							"Invalid class prefix \"{prefix}\", only letters, digits, \"-\" and \"_\" are allowed"
+						));
+					}
+					cli.html.class_prefix = prefix;
+				},
+				"--stylesheet" => {
+					let path = value()?;
+					cli.html.stylesheet_href = Some(path.clone());
+					cli.stylesheet = Some(PathBuf::from(path));
+				},
+				"--css-variables" => {
+					cli.html.css_variables.get_or_insert_with(CssVariables::default);
+				},
+				"--light-theme" => {
+					cli.html.css_variables.get_or_insert_with(CssVariables::default).light = Self::theme(&value()?)?.palette();
+				},
+				"--dark-theme" => {
+					cli.html.css_variables.get_or_insert_with(CssVariables::default).dark = Self::theme(&value()?)?.palette();
+				},
+				"--font-size" => {
+					let font_size = value()?;
+					cli.svg.font_size = font_size
+						.parse::<f32>()
+						.ok()
+						.filter(|size| *size > 0.0)
+						.ok_or_else(|| format!("Invalid font size \"{font_size}\""))?;
+				},
+				"--window-chrome" => cli.svg.window_chrome = true,
+				"--markdown-code" => cli.markdown.mode = MarkdownMode::Code,
+				"--markdown-ansi" => cli.markdown.ansi = true,
+				"--latex-listings" => cli.latex.environment = LatexEnvironment::Listings,
+				"--preamble" => cli.preamble = Some(PathBuf::from(value()?)),
+				"--text-links" => cli.text.links = true,
+				"--json" => {
+					cli.json.content = match value()?.as_str() {
+						"nodes" => JsonContent::Nodes,
+						"runs" => JsonContent::Runs,
+						"both" => JsonContent::Both,
+						other => return Err(format!("Invalid json content \"{other}\", expected nodes, runs or both")),
+					}
+				},
+				"--asciicast" => cli.asciicast = true,
+				"--keep-frames" => cli.carriage_return = CarriageReturn::KeepFrames,
+				"--link-schemes" => {
+					link_policy.schemes =
+						value()?.split(',').map(str::trim).filter(|scheme| !scheme.is_empty()).map(String::from).collect();
+				},
+				"--link-nofollow" => link_policy.rel = Some(String::from(LinkPolicy::NOFOLLOW)),
+				"--link-target" => link_policy.target = Some(value()?),
+				"--file-base-url" => link_policy.file_base_url = Some(value()?),
+				"--show-rejected-links" => link_policy.rejected = RejectedLink::Text,
+				"-h" | "--help" => cli.help = true,
+				_ => return Err(format!("Unknown option \"{arg}\"")),
+			}
+		}
+
+		Ok(cli)
+	}
+
+	fn theme(name: &str) -> Result<Theme, String> {
+		Theme::by_name(name).ok_or_else(|| {
+			let names = Theme::ALL.iter().map(|theme| theme.name).collect::<Vec<_>>().join(", ");
+			format!("Unknown theme \"{name}\", expected one of {names}")
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn parse(args: &[&str]) -> Result<Cli, String> {
+		Cli::parse(args.iter().map(|arg| arg.to_string()))
+	}
+
+	#[test]
+	fn parse_test() {
+		assert_eq!(parse(&[]), Ok(Cli::default()));
+		assert_eq!(
+			parse(&["--keep-frames"]),
+			Ok(Cli {
+				carriage_return: CarriageReturn::KeepFrames,
+				..Cli::default()
+			})
+		);
+		assert_eq!(
+			parse(&["-h"]),
+			Ok(Cli {
+				help: true,
+				..Cli::default()
+			})
+		);
+		assert_eq!(parse(&["--nope"]), Err(String::from("Unknown option \"--nope\"")));
+	}
+
+	#[test]
+	fn parse_svg_test() {
+		let cli = parse(&[
+			"--format",
+			"svg",
+			"--font-size=16",
+			"--window-chrome",
+			"--title",
+			"first",
+		])
+		.unwrap();
+		assert_eq!(cli.format, Format::Svg);
+		assert_eq!(
+			cli.svg,
+			SvgOptions {
+				font_size: 16.0,
+				window_chrome: true,
+				title: TitleSelection::First,
+				..SvgOptions::default()
+			}
+		);
+		assert_eq!(
+			parse(&["--format", "png"]).map(|cli| cli.format),
+			Err(String::from("Unknown format \"png\", expected html, svg, text, markdown, latex, rtf, json or ansi"))
+		);
+		assert_eq!(parse(&["--font-size", "-1"]).map(|cli| cli.format), Err(String::from("Invalid font size \"-1\"")));
+	}
+
+	#[test]
+	fn parse_colors_test() {
+		assert_eq!(parse(&["--colors", "256"]).map(|cli| cli.color_depth), Ok(ColorDepth::Palette256));
+		assert_eq!(parse(&["--colors=16"]).map(|cli| cli.color_depth), Ok(ColorDepth::Ansi16));
+		assert_eq!(parse(&["--colors", "24bit"]).map(|cli| cli.color_depth), Ok(ColorDepth::TrueColor));
+		assert_eq!(
+			parse(&["--colors", "8"]).map(|cli| cli.color_depth),
+			Err(String::from("Invalid colors \"8\", expected truecolor, 256 or 16"))
+		);
+	}
+
+	#[test]
+	fn parse_text_test() {
+		let cli = parse(&["--format", "text", "--text-links"]).unwrap();
+		assert_eq!(cli.format, Format::Text);
+		assert_eq!(cli.text, TextOptions { links: true });
+	}
+
+	#[test]
+	fn parse_json_test() {
+		let cli = parse(&["--format", "json", "--json=runs"]).unwrap();
+		assert_eq!(cli.format, Format::Json);
+		assert_eq!(cli.json.content, JsonContent::Runs);
+		assert_eq!(parse(&["--format", "ansi"]).map(|cli| cli.format), Ok(Format::Ansi));
+		assert_eq!(
+			parse(&["--json", "all"]).map(|cli| cli.json),
+			Err(String::from("Invalid json content \"all\", expected nodes, runs or both"))
+		);
+	}
+
+	#[test]
+	fn parse_markdown_test() {
+		let cli = parse(&["--format=md", "--markdown-code", "--markdown-ansi"]).unwrap();
+		assert_eq!(cli.format, Format::Markdown);
+		assert_eq!(
+			cli.markdown,
+			MarkdownOptions {
+				mode: MarkdownMode::Code,
+				ansi: true,
+			}
+		);
+	}
+
+	#[test]
+	fn parse_latex_test() {
+		let cli = parse(&["--format", "latex", "--latex-listings", "--preamble=colors.tex"]).unwrap();
+		assert_eq!(cli.format, Format::Latex);
+		assert_eq!(cli.latex.environment, LatexEnvironment::Listings);
+		assert_eq!(cli.preamble, Some(PathBuf::from("colors.tex")));
+	}
+
+	#[test]
+	fn parse_rtf_test() {
+		let cli = parse(&["--format", "rtf", "--font", "'Fira Code', monospace"]).unwrap();
+		assert_eq!(cli.format, Format::Rtf);
+		assert_eq!(cli.rtf.font_family, "Fira Code");
+		assert_eq!(parse(&["--font", ",monospace"]).unwrap().rtf, RtfOptions::default());
+	}
+
+	#[test]
+	fn parse_asciicast_test() {
+		let cli = parse(&["--asciicast", "--format=svg"]).unwrap();
+		assert!(cli.asciicast);
+		assert_eq!(cli.format, Format::Svg);
+	}
+
+	#[test]
+	fn parse_theme_test() {
+		assert_eq!(parse(&["--theme", "nord"]).map(|cli| cli.theme), Ok(Some(Theme::NORD)));
+		assert_eq!(parse(&["--theme=Dracula"]).map(|cli| cli.theme), Ok(Some(Theme::DRACULA)));
+		assert!(
+			parse(&["--theme", "nope"])
+				.unwrap_err()
+				.starts_with("Unknown theme \"nope\", expected one of xterm, vga, solarized-dark")
+		);
+	}
+
+	#[test]
+	fn parse_theme_file_test() {
+		let cli = parse(&["--theme-file", "settings.json", "--theme-scheme=One Half Dark"]).unwrap();
+		assert_eq!(cli.theme_file, Some(PathBuf::from("settings.json")));
+		assert_eq!(cli.theme_scheme, Some(String::from("One Half Dark")));
+		assert_eq!(
+			parse(&["--theme-file"]).map(|cli| cli.theme_file),
+			Err(String::from("Missing value for option \"--theme-file\""))
+		);
+	}
+
+	#[test]
+	fn parse_document_test() {
+		assert_eq!(
+			parse(&[
+				"--document",
+				"--font",
+				"'Fira Code', monospace",
+				"--title",
+				"first",
+				"--title-header"
+			])
+			.map(|cli| cli.html),
+			Ok(HtmlOptions {
+				document: true,
+				font_family: String::from("'Fira Code', monospace"),
+				title: TitleSelection::First,
+				title_header: true,
+				..HtmlOptions::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--font", "x;}body{display:none"]).map(|cli| cli.html),
+			Err(String::from("Invalid font \"x;}body{display:none\""))
+		);
+		assert_eq!(
+			parse(&["--title=middle"]).map(|cli| cli.html),
+			Err(String::from("Invalid title \"middle\", expected first or last"))
+		);
+	}
+
+	#[test]
+	fn parse_email_test() {
+		assert_eq!(parse(&["--email"]).map(|cli| cli.html.style_mode), Ok(StyleMode::Email));
+		assert_eq!(parse(&["--email", "--email"]).map(|cli| cli.html.style_mode), Ok(StyleMode::Email));
+		assert_eq!(
+			parse(&["--classes", "--email"]).map(|cli| cli.html),
+			Err(String::from("Options \"--classes\" and \"--email\" can't be used together"))
+		);
+		assert_eq!(
+			parse(&["--email", "--classes"]).map(|cli| cli.html),
+			Err(String::from("Options \"--classes\" and \"--email\" can't be used together"))
+		);
+	}
+
+	#[test]
+	fn parse_classes_test() {
+		let cli = parse(&["--classes", "--class-prefix", "log_", "--stylesheet=out/log.css"]).unwrap();
+		assert_eq!(cli.html.style_mode, StyleMode::Classes);
+		assert_eq!(cli.html.class_prefix, "log_");
+		assert_eq!(cli.html.stylesheet_href, Some(String::from("out/log.css")));
+		assert_eq!(cli.stylesheet, Some(PathBuf::from("out/log.css")));
+		assert_eq!(
+			parse(&["--class-prefix", "a{b"]).map(|cli| cli.html),
+			Err(String::from("Invalid class prefix \"a{b\", only letters, digits, \"-\" and \"_\" are allowed"))
+		);
+	}
+
+	#[test]
+	fn parse_css_variables_test() {
+		assert_eq!(parse(&["--css-variables"]).map(|cli| cli.html.css_variables), Ok(Some(CssVariables::default())));
+		assert_eq!(
+			parse(&["--light-theme", "xterm", "--dark-theme=dracula"]).map(|cli| cli.html.css_variables),
+			Ok(Some(CssVariables {
+				light: Theme::XTERM.palette(),
+				dark: Theme::DRACULA.palette(),
+			}))
+		);
+		assert!(parse(&["--dark-theme", "nope"]).unwrap_err().starts_with("Unknown theme \"nope\""));
+	}
+
+	#[test]
+	fn parse_link_policy_test() {
+		assert_eq!(
+			parse(&[
+				"--link-schemes",
+				"https, mailto",
+				"--link-nofollow",
+				"--link-target=_blank",
+				"--file-base-url",
+				"https://example.com",
+				"--show-rejected-links",
+			])
+			.map(|cli| cli.html.link_policy),
+			Ok(LinkPolicy {
+				schemes: vec![String::from("https"), String::from("mailto")],
+				rel: Some(String::from("noopener nofollow")),
+				target: Some(String::from("_blank")),
+				file_base_url: Some(String::from("https://example.com")),
+				rejected: RejectedLink::Text,
+			})
+		);
+		assert_eq!(
+			parse(&["--link-target"]).map(|cli| cli.html.link_policy),
+			Err(String::from("Missing value for option \"--link-target\""))
+		);
+	}
+}
@@ -0,0 +1,946 @@
+use std::collections::BTreeMap;
+
+use crate::{
+	links::{LinkPolicy, RejectedLink},
+	nodes::AnsiNode,
+	palette::Palette,
+	screen::{Cell, Hyperlink, Screen, TitleSelection},
+	styles::StyleNode,
+	themes::Theme,
+};
+
+pub fn escape_char(c: char) -> Option<&'static str> {
+	match c {
+		'&' => Some("&amp;"),
+		'<' => Some("&lt;"),
+		'>' => Some("&gt;"),
+		'"' => Some("&quot;"),
+		'\'' => Some("&#39;"),
+		_ => None,
+	}
+}
+
+pub fn push_escaped(html: &mut String, text: &str) {
+	for c in text.chars() {
+		match escape_char(c) {
+			Some(entity) => html.push_str(entity),
+			None => html.push(c),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StyleMode {
+	/// Inline style attributes on every span
+	#[default]
+	Inline,
+	/// Classes like sv-bold sv-fg-1 plus a stylesheet with the classes that were used
+	Classes,
+	/// Inline styles email clients support plus <font> tags, wrapped in a table with a bgcolor.
+	/// There is no stylesheet so CSS variables and blinking are dropped.
+	Email,
+}
+
+/// The palettes behind the CSS custom properties of the 16 ANSI colors and the default colors.
+/// The dark palette is used when the reader prefers a dark color scheme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssVariables {
+	pub light: Palette,
+	pub dark: Palette,
+}
+
+impl Default for CssVariables {
+	fn default() -> Self {
+		Self {
+			light: Theme::SOLARIZED_LIGHT.palette(),
+			dark: Theme::SOLARIZED_DARK.palette(),
+		}
+	}
+}
+
+impl CssVariables {
+	fn push_declarations(css: &mut String, palette: &Palette, prefix: &str) {
+		css.push_str(":root{");
+		for index in 0..16 {
+			if let Some(name) = Palette::ansi_name(index) {
+				css.push_str(&format!("--{prefix}{name}:"));
+				StyleNode::append_rgb(css, palette.get(index));
+				css.push(';');
+			}
+		}
+		for (name, color) in [("fg", palette.foreground), ("bg", palette.background)] {
+			if let Some(color) = color {
+				css.push_str(&format!("--{prefix}{name}:"));
+				StyleNode::append_rgb(css, color);
+				css.push(';');
+			}
+		}
+		css.push('}');
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlOptions {
+	pub link_policy: LinkPolicy,
+	/// Wrap the output in a full HTML document
+	pub document: bool,
+	pub title: TitleSelection,
+	/// Show the terminal title as a heading above the output
+	pub title_header: bool,
+	pub style_mode: StyleMode,
+	/// Goes in front of every class name so it needs to be valid in a CSS class selector
+	pub class_prefix: String,
+	/// Link to the stylesheet of the class mode instead of embedding it
+	pub stylesheet_href: Option<String>,
+	/// Reference the 16 ANSI colors and the default colors as CSS custom properties like var(--sv-red)
+	pub css_variables: Option<CssVariables>,
+	/// The font stack of a document, inserted into the CSS as is
+	pub font_family: String,
+}
+
+impl Default for HtmlOptions {
+	fn default() -> Self {
+		Self {
+			link_policy: LinkPolicy::default(),
+			document: false,
+			title: TitleSelection::default(),
+			title_header: false,
+			style_mode: StyleMode::default(),
+			class_prefix: String::from(Self::DEFAULT_CLASS_PREFIX),
+			stylesheet_href: None,
+			css_variables: None,
+			font_family: String::from(Self::DEFAULT_FONT_FAMILY),
+		}
+	}
+}
+
+impl HtmlOptions {
+	pub const DEFAULT_CLASS_PREFIX: &str = "sv-";
+	pub const DEFAULT_FONT_FAMILY: &str = "ui-monospace,SFMono-Regular,Menlo,Consolas,'Liberation Mono',monospace";
+}
+
+pub struct HtmlRenderer<'a> {
+	html: String,
+	options: &'a HtmlOptions,
+	links: &'a [Hyperlink],
+	palette: Palette,
+	// The url each link resolves to under the link policy
+	urls: Vec<Option<String>>,
+	open: Option<StyleNode>,
+	open_tag: String,
+	open_link: Option<usize>,
+	pending_newlines: usize,
+	// Class name to CSS of every class used in the class mode
+	used_rules: BTreeMap<(usize, String), String>,
+	blink: bool,
+}
+
+impl<'a> HtmlRenderer<'a> {
+	pub fn render(nodes: &[AnsiNode]) -> String {
+		HtmlRenderer::render_screen(&Screen::from_nodes(nodes), &HtmlOptions::default())
+	}
+
+	pub fn render_screen(screen: &'a Screen, options: &'a HtmlOptions) -> String {
+		Self::render_with_stylesheet(screen, options).0
+	}
+
+	/// Returns the html and, in the class mode, the stylesheet for it.
+	/// The stylesheet is only embedded in the html when there is no `stylesheet_href`.
+	pub fn render_with_stylesheet(screen: &'a Screen, options: &'a HtmlOptions) -> (String, String) {
+		Self::render_lines(screen, screen.lines(), options)
+	}
+
+	/// Renders only the rows of a screen with a fixed height, leaving out the lines scrolled off the top
+	pub fn render_viewport(screen: &'a Screen, options: &'a HtmlOptions) -> String {
+		Self::render_lines(screen, screen.viewport().into_iter(), options).0
+	}
+
+	fn render_lines(
+		screen: &'a Screen,
+		lines: impl Iterator<Item = &'a [Option<Cell>]>,
+		options: &'a HtmlOptions,
+	) -> (String, String) {
+		// An email sets its own background so unknown default colors fall back to xterm's
+		let mut palette = screen.palette().clone();
+		if options.style_mode == StyleMode::Email {
+			palette.foreground = palette.foreground.or(Some(Theme::XTERM.foreground));
+			palette.background = palette.background.or(Some(Theme::XTERM.background));
+		}
+
+		let mut renderer = Self {
+			html: String::new(),
+			options,
+			links: screen.links(),
+			palette,
+			urls: screen.links().iter().map(|link| options.link_policy.resolve(&link.url)).collect(),
+			open: None,
+			open_tag: String::new(),
+			open_link: None,
+			pending_newlines: 0,
+			used_rules: BTreeMap::new(),
+			blink: false,
+		};
+
+		for (row, line) in lines.enumerate() {
+			if row > 0 {
+				renderer.pending_newlines += 1;
+			}
+
+			for cell in line {
+				match cell {
+					Some(cell) => renderer.push_char(cell.ch, &cell.style, cell.link),
+					None => renderer.push_char(' ', &StyleNode::default(), None),
+				}
+			}
+		}
+
+		renderer.close_span();
+		renderer.close_link();
+		renderer.flush_newlines();
+
+		let title = screen.title(options.title);
+		if options.style_mode == StyleMode::Email {
+			return (renderer.email(title), String::new());
+		}
+
+		let palette = screen.palette();
+		let classes = options.style_mode == StyleMode::Classes;
+
+		// Text without colors takes the terminal's default colors when they are known.
+		// A document always looks like a terminal so it falls back to xterm's colors.
+		let (foreground, background) = if options.document {
+			(palette.foreground.or(Some(Theme::XTERM.foreground)), palette.background.or(Some(Theme::XTERM.background)))
+		} else {
+			(palette.foreground, palette.background)
+		};
+		let mut background_css = String::new();
+		let mut terminal_css = String::new();
+		if options.css_variables.is_some() {
+			let prefix = &options.class_prefix;
+			terminal_css.push_str(&format!("color:var(--{prefix}fg);"));
+			background_css.push_str(&format!("background:var(--{prefix}bg);"));
+		} else {
+			if let Some(color) = foreground {
+				terminal_css.push_str("color:");
+				StyleNode::append_rgb(&mut terminal_css, color);
+				terminal_css.push(';');
+			}
+			if let Some(color) = background {
+				background_css.push_str("background:");
+				StyleNode::append_rgb(&mut background_css, color);
+				background_css.push(';');
+			}
+		}
+		terminal_css.push_str(&background_css);
+
+		let mut wrappers = Vec::new();
+		if options.document {
+			wrappers.push(("document", format!("margin:0;{background_css}")));
+			terminal_css.push_str(&format!("font-family:{};margin:0;padding:1em;", options.font_family));
+		}
+		if !terminal_css.is_empty() {
+			wrappers.push(("terminal", terminal_css));
+		}
+
+		let needs_stylesheet = classes || options.css_variables.is_some() || (options.document && renderer.blink);
+		let stylesheet = if needs_stylesheet {
+			renderer.stylesheet(&wrappers)
+		} else {
+			String::new()
+		};
+		let embedded_stylesheet = options.stylesheet_href.is_none() && !stylesheet.is_empty();
+
+		let mut html = String::with_capacity(renderer.html.len() + stylesheet.len() + 200);
+
+		if options.document {
+			html.push_str("<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+			if let Some(title) = title {
+				html.push_str("<title>");
+				push_escaped(&mut html, title);
+				html.push_str("</title>\n");
+			}
+			if !stylesheet.is_empty()
+				&& let Some(href) = &options.stylesheet_href
+			{
+				html.push_str("<link rel=\"stylesheet\" href=\"");
+				push_escaped(&mut html, href);
+				html.push_str("\">\n");
+			}
+		}
+
+		if embedded_stylesheet {
+			html.push_str("<style>\n");
+			html.push_str(&stylesheet);
+			html.push_str("</style>\n");
+		}
+
+		if options.document {
+			html.push_str("</head>\n");
+			renderer.push_wrapper(&mut html, "body", &wrappers, "document");
+			html.push('\n');
+		}
+
+		if options.title_header
+			&& let Some(title) = title
+		{
+			html.push_str("<h1>");
+			push_escaped(&mut html, title);
+			html.push_str("</h1>\n");
+		}
+
+		// A document keeps the whitespace of the output in a <pre> while a fragment leaves that to the page
+		let wrapper_tag = if options.document { "pre" } else { "div" };
+		let has_wrapper = renderer.push_wrapper(&mut html, wrapper_tag, &wrappers, "terminal");
+
+		html.push_str(&renderer.html);
+
+		if has_wrapper {
+			html.push_str(&format!("</{wrapper_tag}>"));
+		}
+
+		if options.document {
+			html.push_str("\n</body>\n</html>");
+		}
+
+		(html, stylesheet)
+	}
+
+	// Email clients drop <style> and body styles so the colors go on a full width table and the <pre> in it
+	fn email(&self, title: Option<&str>) -> String {
+		let options = self.options;
+		let foreground = self.palette.foreground.unwrap_or(Theme::XTERM.foreground).to_hex();
+		let background = self.palette.background.unwrap_or(Theme::XTERM.background).to_hex();
+		let mut html = String::with_capacity(self.html.len() + 500);
+
+		if options.document {
+			html.push_str("<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+			if let Some(title) = title {
+				html.push_str("<title>");
+				push_escaped(&mut html, title);
+				html.push_str("</title>\n");
+			}
+			html.push_str("</head>\n<body style=\"margin:0;\">\n");
+		}
+
+		if options.title_header
+			&& let Some(title) = title
+		{
+			html.push_str("<h1>");
+			push_escaped(&mut html, title);
+			html.push_str("</h1>\n");
+		}
+
+		html.push_str(&format!(
+			"<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" border=\"0\" bgcolor=\"{background}\" style=\"background-color:{background};\"><tr><td style=\"padding:16px;\"><pre style=\"margin:0;font-family:"
+		));
+		push_escaped(&mut html, &options.font_family);
+		html.push_str(&format!(";color:{foreground};\"><font color=\"{foreground}\">"));
+		html.push_str(&self.html);
+		html.push_str("</font></pre></td></tr></table>");
+
+		if options.document {
+			html.push_str("\n</body>\n</html>");
+		}
+
+		html
+	}
+
+	// Opens the tag with the declarations of the named wrapper as a class or an inline style
+	fn push_wrapper(&self, html: &mut String, tag: &str, wrappers: &[(&str, String)], name: &str) -> bool {
+		let Some((_, css)) = wrappers.iter().find(|(wrapper, _)| *wrapper == name) else {
+			if tag == "body" {
+				html.push_str("<body>");
+			}
+			return false;
+		};
+
+		html.push('<');
+		html.push_str(tag);
+		if self.options.style_mode == StyleMode::Classes {
+			html.push_str(" class=\"");
+			push_escaped(html, &self.options.class_prefix);
+			html.push_str(name);
+		} else {
+			html.push_str(" style=\"");
+			push_escaped(html, css);
+		}
+		html.push_str("\">");
+		true
+	}
+
+	fn stylesheet(&self, wrappers: &[(&str, String)]) -> String {
+		let prefix = &self.options.class_prefix;
+		let classes = self.options.style_mode == StyleMode::Classes;
+		let mut css = String::new();
+
+		if let Some(variables) = &self.options.css_variables {
+			CssVariables::push_declarations(&mut css, &variables.light, prefix);
+			css.push_str("\n@media (prefers-color-scheme:dark){");
+			CssVariables::push_declarations(&mut css, &variables.dark, prefix);
+			css.push_str("}\n");
+		}
+
+		if classes {
+			for (name, rule) in wrappers {
+				css.push_str(&format!(".{prefix}{name}{{{rule}}}\n"));
+			}
+		}
+		for ((_, class), rule) in &self.used_rules {
+			css.push_str(&format!(".{prefix}{class}{{{rule}}}\n"));
+		}
+		if self.blink && (classes || self.options.document) {
+			css.push_str("@keyframes blink{50%{opacity:0;}}\n");
+		}
+
+		css
+	}
+
+	fn push_char(&mut self, c: char, style: &StyleNode, link: Option<usize>) {
+		// Email clients ignore the styles that would hide text so it is written as spaces instead
+		let c = if self.options.style_mode == StyleMode::Email && style.hidden() {
+			' '
+		} else {
+			c
+		};
+
+		// Anchors wrap spans so a style change inside a link never splits the link
+		if self.open_link != link {
+			self.close_span();
+			self.close_link();
+			self.flush_newlines();
+
+			if let Some(link) = link {
+				self.open_link = Some(link);
+
+				if let Some(url) = &self.urls[link] {
+					self.html.push_str("<a href=\"");
+					push_escaped(&mut self.html, url);
+					self.html.push('"');
+					if let Some(rel) = &self.options.link_policy.rel {
+						self.html.push_str(" rel=\"");
+						push_escaped(&mut self.html, rel);
+						self.html.push('"');
+					}
+					if let Some(target) = &self.options.link_policy.target {
+						self.html.push_str(" target=\"");
+						push_escaped(&mut self.html, target);
+						self.html.push('"');
+					}
+					self.html.push('>');
+				}
+			}
+		}
+
+		let style = style.effective();
+		if self.open != Some(style) {
+			let tag = if style.is_default() {
+				String::new()
+			} else {
+				let variables = self.options.css_variables.as_ref().map(|_| self.options.class_prefix.as_str());
+				let rules = style.rules(&self.palette, variables);
+				self.blink |= rules.iter().any(|rule| rule.class == "blink" || rule.class == "rapid-blink");
+				match self.options.style_mode {
+					StyleMode::Inline => style.to_html_with_rules(&rules),
+					StyleMode::Classes => {
+						let tag = style.to_html_with_classes(&rules, &self.options.class_prefix);
+						for rule in rules {
+							self.used_rules.insert((rule.order(), rule.class), rule.css);
+						}
+						tag
+					},
+					StyleMode::Email => style.to_html_for_email(&self.palette),
+				}
+			};
+
+			if self.open.is_some() && tag == self.open_tag {
+				// A different style that looks the same, e.g. red and palette color 1
+				self.open = Some(style);
+				self.flush_newlines();
+			} else {
+				self.close_span();
+				self.flush_newlines();
+
+				if !tag.is_empty() {
+					self.html.push_str(&tag);
+					self.open = Some(style);
+					self.open_tag = tag;
+				}
+			}
+		} else {
+			self.flush_newlines();
+		}
+
+		match escape_char(c) {
+			Some(entity) => self.html.push_str(entity),
+			None => self.html.push(c),
+		}
+	}
+
+	// Newlines are held back so a span that continues on the next line isn't closed and reopened
+	fn flush_newlines(&mut self) {
+		for _ in 0..self.pending_newlines {
+			self.html.push('\n');
+		}
+		self.pending_newlines = 0;
+	}
+
+	fn close_span(&mut self) {
+		if let Some(style) = self.open.take() {
+			if self.options.style_mode == StyleMode::Email {
+				self.html.push_str(&style.to_html_close_for_email(&self.palette));
+			} else {
+				self.html.push_str(&style.to_html_close());
+			}
+		}
+	}
+
+	fn close_link(&mut self) {
+		if let Some(link) = self.open_link.take() {
+			if self.urls[link].is_some() {
+				self.html.push_str("</a>");
+			} else if self.options.link_policy.rejected == RejectedLink::Text {
+				self.html.push_str(" (");
+				push_escaped(&mut self.html, &self.links[link].url);
+				self.html.push(')');
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8]) -> String {
+		HtmlRenderer::render(&TerminalOutputParser::parse_to_nodes(input))
+	}
+
+	#[test]
+	fn plain_text_test() {
+		assert_eq!(render(b"hello world"), String::from("hello world"));
+		assert_eq!(render(b""), String::new());
+	}
+
+	#[test]
+	fn styled_text_test() {
+		assert_eq!(render(b"\x1B[31mred\x1B[0m plain"), String::from("<span style=\"color:#cd0000;\">red</span> plain"));
+		assert_eq!(
+			render(b"\x1B[1;4mbold\x1B[0m"),
+			String::from("<span style=\"font-weight:bold;text-decoration:underline;\">bold</span>")
+		);
+	}
+
+	#[test]
+	fn extended_colors_test() {
+		assert_eq!(render(b"\x1B[38;5;196mA"), String::from("<span style=\"color:#f00;\">A</span>"));
+		assert_eq!(render(b"\x1B[38:5:196mA"), String::from("<span style=\"color:#f00;\">A</span>"));
+		assert_eq!(render(b"\x1B[38;2;255;0;128mA"), String::from("<span style=\"color:#ff0080;\">A</span>"));
+		assert_eq!(render(b"\x1B[48;2;17;34;51mA"), String::from("<span style=\"background:#123;\">A</span>"));
+	}
+
+	#[test]
+	fn unclosed_style_test() {
+		assert_eq!(render(b"\x1B[32mgreen"), String::from("<span style=\"color:#00cd00;\">green</span>"));
+	}
+
+	#[test]
+	fn consecutive_styles_test() {
+		assert_eq!(
+			render(b"\x1B[31mA\x1B[34mB"),
+			String::from("<span style=\"color:#cd0000;\">A</span><span style=\"color:#00e;\">B</span>")
+		);
+		assert_eq!(render(b"\x1B[31m\x1B[0mA"), String::from("A"));
+	}
+
+	#[test]
+	fn cumulative_styles_test() {
+		assert_eq!(
+			render(b"\x1B[1m\x1B[31mA\x1B[22mB"),
+			String::from("<span style=\"font-weight:bold;color:#f00;\">A</span><span style=\"color:#cd0000;\">B</span>")
+		);
+		assert_eq!(
+			render(b"\x1B[3mA\x1B[4mB\x1B[23mC\x1B[0mD"),
+			String::from(
+				"<span style=\"font-style:italic;\">A</span><span style=\"font-style:italic;text-decoration:underline;\">B</span><span style=\"text-decoration:underline;\">C</span>D"
+			)
+		);
+	}
+
+	#[test]
+	fn unchanged_style_keeps_span_test() {
+		assert_eq!(render(b"\x1B[31mA\x1B[31mB"), String::from("<span style=\"color:#cd0000;\">AB</span>"));
+		assert_eq!(render(b"\x1B[31mA\x1B[0m\x1B[31mB"), String::from("<span style=\"color:#cd0000;\">AB</span>"));
+		assert_eq!(render(b"A\x1B[32m\x1B[39mB"), String::from("AB"));
+	}
+
+	#[test]
+	fn subscript_test() {
+		assert_eq!(render(b"H\x1B[74m2\x1B[75mO"), String::from("H<sub style=\"\">2</sub>O"));
+	}
+
+	#[test]
+	fn escape_test() {
+		assert_eq!(
+			render(b"error[E0308]: expected `Vec<T>`, found `&&str`"),
+			String::from("error[E0308]: expected `Vec&lt;T&gt;`, found `&amp;&amp;str`")
+		);
+		assert_eq!(
+			render(b"\x1B[1;31merror\x1B[0m: Result<Option<&'a str>, Box<dyn Error>>"),
+			String::from(
+				"<span style=\"font-weight:bold;color:#f00;\">error</span>: Result&lt;Option&lt;&amp;&#39;a str&gt;, Box&lt;dyn Error&gt;&gt;"
+			)
+		);
+		assert_eq!(
+			render(b"<script>alert(\"x\")</script><img src=x onerror=alert(1)>"),
+			String::from("&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt;&lt;img src=x onerror=alert(1)&gt;")
+		);
+		assert_eq!(render(b"&amp &lt; &#x3c &# &;"), String::from("&amp;amp &amp;lt; &amp;#x3c &amp;# &amp;;"));
+	}
+
+	#[test]
+	fn multiline_spans_test() {
+		assert_eq!(render(b"\x1B[31mA\nB\x1B[0m"), String::from("<span style=\"color:#cd0000;\">A\nB</span>"));
+		assert_eq!(render(b"\x1B[31mA\x1B[0m\n"), String::from("<span style=\"color:#cd0000;\">A</span>\n"));
+		assert_eq!(
+			render(b"\x1B[31mA\n\x1B[32mB\x1B[0m"),
+			String::from("<span style=\"color:#cd0000;\">A</span>\n<span style=\"color:#00cd00;\">B</span>")
+		);
+	}
+
+	#[test]
+	fn cursor_movement_test() {
+		assert_eq!(
+			render(b"\x1B[32m[   ]\x1B[0m\x1B[1G\x1B[2C\x1B[1;32mOK\x1B[0m"),
+			String::from(
+				"<span style=\"color:#00cd00;\">[ </span><span style=\"font-weight:bold;color:#0f0;\">OK</span><span style=\"color:#00cd00;\">]</span>"
+			)
+		);
+		assert_eq!(render(b"old\x1B[2J\x1B[Hnew"), String::from("new"));
+	}
+
+	#[test]
+	fn man_page_overstrike_test() {
+		assert_eq!(
+			render(b"N\x08NA\x08AM\x08ME\x08E\n       ls - list _\x08d_\x08i_\x08r contents"),
+			String::from(
+				"<span style=\"font-weight:bold;\">NAME</span>\n       ls - list <span style=\"text-decoration:underline;\">dir</span> contents"
+			)
+		);
+	}
+
+	#[test]
+	fn hyperlink_test() {
+		assert_eq!(
+			render(b"see \x1B]8;;https://example.com/?a=1&b=\"2\"\x07docs\x1B]8;;\x07!"),
+			String::from("see <a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">docs</a>!")
+		);
+		assert_eq!(
+			render(b"\x1B[31mred \x1B]8;;http://a\x07li\x1B[1mnk\x1B]8;;\x07 after\x1B[0m"),
+			String::from(
+				"<span style=\"color:#cd0000;\">red </span><a href=\"http://a\"><span style=\"color:#cd0000;\">li</span><span style=\"font-weight:bold;color:#f00;\">nk</span></a><span style=\"font-weight:bold;color:#f00;\"> after</span>"
+			)
+		);
+	}
+
+	#[test]
+	fn link_policy_test() {
+		let render_with = |input: &[u8], link_policy: LinkPolicy| {
+			let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input));
+			HtmlRenderer::render_screen(
+				&screen,
+				&HtmlOptions {
+					link_policy,
+					..HtmlOptions::default()
+				},
+			)
+		};
+
+		assert_eq!(render(b"\x1B]8;;javascript:alert(document.cookie)\x07click\x1B]8;;\x07 me"), String::from("click me"));
+		assert_eq!(
+			render(b"\x1B[31m\x1B]8;;data:text/html,<script>\x07click\x1B]8;;\x07\x1B[0m"),
+			String::from("<span style=\"color:#cd0000;\">click</span>")
+		);
+		assert_eq!(
+			render_with(
+				b"\x1B]8;;javascript:alert(1)\x07click\x1B]8;;\x07 me",
+				LinkPolicy {
+					rejected: RejectedLink::Text,
+					..LinkPolicy::default()
+				}
+			),
+			String::from("click (javascript:alert(1)) me")
+		);
+		assert_eq!(
+			render_with(
+				b"\x1B]8;;https://example.com\x07docs\x1B]8;;\x07",
+				LinkPolicy {
+					rel: Some(String::from(LinkPolicy::NOFOLLOW)),
+					target: Some(String::from("_blank")),
+					..LinkPolicy::default()
+				}
+			),
+			String::from("<a href=\"https://example.com\" rel=\"noopener nofollow\" target=\"_blank\">docs</a>")
+		);
+		assert_eq!(
+			render_with(
+				b"\x1B]8;;file://ci-runner/src/main.rs\x07src/main.rs\x1B]8;;\x07",
+				LinkPolicy {
+					file_base_url: Some(String::from("https://git.example.com/blob/main")),
+					..LinkPolicy::default()
+				}
+			),
+			String::from("<a href=\"https://git.example.com/blob/main/src/main.rs\">src/main.rs</a>")
+		);
+	}
+
+	#[test]
+	fn split_hyperlink_test() {
+		// The same id continues one anchor across the line break
+		assert_eq!(
+			render(b"\x1B]8;id=x;http://a\x07long\nlink\x1B]8;;\x07"),
+			String::from("<a href=\"http://a\">long\nlink</a>")
+		);
+		assert_eq!(
+			render(b"\x1B]8;id=x;http://a\x07long\x1B]8;;\x07\n\x1B]8;id=x;http://a\x07link\x1B]8;;\x07"),
+			String::from("<a href=\"http://a\">long\nlink</a>")
+		);
+		assert_eq!(
+			render(b"\x1B]8;;http://a\x07long\x1B]8;;\x07\n\x1B]8;;http://a\x07link\x1B]8;;\x07"),
+			String::from("<a href=\"http://a\">long</a>\n<a href=\"http://a\">link</a>")
+		);
+	}
+
+	#[test]
+	fn title_test() {
+		let render_with = |input: &[u8], options: HtmlOptions| {
+			HtmlRenderer::render_screen(&Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input)), &options)
+		};
+		let input = b"\x1B]0;cargo build\x07Compiling\x1B]2;cargo <test>\x07";
+
+		assert_eq!(
+			render_with(
+				input,
+				HtmlOptions {
+					document: true,
+					..HtmlOptions::default()
+				}
+			),
+			String::from(
+				"<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>cargo &lt;test&gt;</title>\n</head>\n<body style=\"margin:0;background:#fff;\">\n<pre style=\"color:#000;background:#fff;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,&#39;Liberation Mono&#39;,monospace;margin:0;padding:1em;\">Compiling</pre>\n</body>\n</html>"
+			)
+		);
+		assert_eq!(
+			render_with(
+				input,
+				HtmlOptions {
+					title: TitleSelection::First,
+					title_header: true,
+					..HtmlOptions::default()
+				}
+			),
+			String::from("<h1>cargo build</h1>\nCompiling")
+		);
+		assert_eq!(
+			render_with(
+				b"no title",
+				HtmlOptions {
+					document: true,
+					title_header: true,
+					..HtmlOptions::default()
+				}
+			),
+			String::from(
+				"<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body style=\"margin:0;background:#fff;\">\n<pre style=\"color:#000;background:#fff;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,&#39;Liberation Mono&#39;,monospace;margin:0;padding:1em;\">no title</pre>\n</body>\n</html>"
+			)
+		);
+	}
+
+	#[test]
+	fn palette_test() {
+		assert_eq!(
+			render(b"\x1B[31mA\x1B]4;1;rgb:12/34/56\x07\x1B[38;5;1mB\x1B[91mC"),
+			String::from("<span style=\"color:#123456;\">AB</span><span style=\"color:#f00;\">C</span>")
+		);
+		assert_eq!(
+			render(b"\x1B]10;#eeeeee\x07\x1B]11;rgb:11/11/11\x07A\x1B[7mB"),
+			String::from(
+				"<div style=\"color:#eee;background:#111;\">A<span style=\"color:#111;background:#eee;\">B</span></div>"
+			)
+		);
+		assert_eq!(render(b"\x1B]11;#111\x07\x1B]111\x07A"), String::from("A"));
+	}
+
+	#[test]
+	fn class_mode_test() {
+		let options = HtmlOptions {
+			style_mode: StyleMode::Classes,
+			..HtmlOptions::default()
+		};
+		let render_with = |input: &[u8], options: &HtmlOptions| {
+			HtmlRenderer::render_with_stylesheet(&Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input)), options)
+		};
+
+		assert_eq!(
+			render_with(b"\x1B[1;31mA\x1B[22mB\x1B[0m \x1B[48;2;255;0;128;5mC\x1B[0m D", &options),
+			(
+				String::from(
+					"<style>\n.sv-bold{font-weight:bold;}\n.sv-blink{animation:blink 1s step-start infinite;}\n.sv-fg-1{color:#cd0000;}\n.sv-fg-9{color:#f00;}\n.sv-bg-rgb-ff0080{background:#ff0080;}\n@keyframes blink{50%{opacity:0;}}\n</style>\n<span class=\"sv-bold sv-fg-9\">A</span><span class=\"sv-fg-1\">B</span> <span class=\"sv-blink sv-bg-rgb-ff0080\">C</span> D"
+				),
+				String::from(
+					".sv-bold{font-weight:bold;}\n.sv-blink{animation:blink 1s step-start infinite;}\n.sv-fg-1{color:#cd0000;}\n.sv-fg-9{color:#f00;}\n.sv-bg-rgb-ff0080{background:#ff0080;}\n@keyframes blink{50%{opacity:0;}}\n"
+				)
+			)
+		);
+
+		// Rules cascade in the same order as inline styles
+		assert_eq!(
+			render_with(b"\x1B[58;5;2;4mA", &options).1,
+			String::from(".sv-underline{text-decoration:underline;}\n.sv-ul-2{text-decoration-color:#00cd00;}\n")
+		);
+
+		// Plain text needs no stylesheet
+		assert_eq!(render_with(b"plain", &options), (String::from("plain"), String::new()));
+
+		// Red and palette color 1 share a class
+		assert_eq!(
+			render_with(b"\x1B[31mA\x1B[38;5;1mB", &options).0,
+			String::from("<style>\n.sv-fg-1{color:#cd0000;}\n</style>\n<span class=\"sv-fg-1\">AB</span>")
+		);
+	}
+
+	#[test]
+	fn class_mode_options_test() {
+		let options = HtmlOptions {
+			style_mode: StyleMode::Classes,
+			class_prefix: String::from("log-"),
+			stylesheet_href: Some(String::from("log.css")),
+			document: true,
+			..HtmlOptions::default()
+		};
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(b"\x1B]11;#111\x07\x1B[3mA\x1B[74mB"));
+
+		assert_eq!(
+			HtmlRenderer::render_with_stylesheet(&screen, &options),
+			(
+				String::from(
+					"<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<link rel=\"stylesheet\" href=\"log.css\">\n</head>\n<body class=\"log-document\">\n<pre class=\"log-terminal\"><span class=\"log-italic\">A</span><sub class=\"log-italic\">B</sub></pre>\n</body>\n</html>"
+				),
+				String::from(
+					".log-document{margin:0;background:#111;}\n.log-terminal{color:#000;background:#111;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,'Liberation Mono',monospace;margin:0;padding:1em;}\n.log-italic{font-style:italic;}\n"
+				)
+			)
+		);
+	}
+
+	#[test]
+	fn empty_stylesheet_test() {
+		let options = HtmlOptions {
+			stylesheet_href: Some(String::from("log.css")),
+			document: true,
+			font_family: String::from("monospace"),
+			..HtmlOptions::default()
+		};
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(b"\x1B[3mA"));
+
+		// Inline styles have nothing to put in a stylesheet so there is nothing to link
+		assert_eq!(
+			HtmlRenderer::render_with_stylesheet(&screen, &options),
+			(
+				String::from(
+					"<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body style=\"margin:0;background:#fff;\">\n<pre style=\"color:#000;background:#fff;font-family:monospace;margin:0;padding:1em;\"><span style=\"font-style:italic;\">A</span></pre>\n</body>\n</html>"
+				),
+				String::new()
+			)
+		);
+	}
+
+	#[test]
+	fn css_variables_test() {
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(b"\x1B[31mA\x1B[0m B"));
+		let mut options = HtmlOptions {
+			css_variables: Some(CssVariables::default()),
+			..HtmlOptions::default()
+		};
+
+		let (html, stylesheet) = HtmlRenderer::render_with_stylesheet(&screen, &options);
+		assert!(stylesheet.starts_with(":root{--sv-black:#073642;--sv-red:#dc322f;"));
+		assert!(stylesheet.contains("--sv-bright-white:#fdf6e3;--sv-fg:#657b83;--sv-bg:#fdf6e3;}\n"));
+		assert!(stylesheet.ends_with("--sv-fg:#839496;--sv-bg:#002b36;}}\n"));
+		assert!(stylesheet.contains("\n@media (prefers-color-scheme:dark){:root{--sv-black:#073642;"));
+		assert_eq!(
+			html,
+			format!(
+				"<style>\n{stylesheet}</style>\n<div style=\"color:var(--sv-fg);background:var(--sv-bg);\"><span style=\"color:var(--sv-red);\">A</span> B</div>"
+			)
+		);
+
+		options.style_mode = StyleMode::Classes;
+		options.stylesheet_href = Some(String::from("log.css"));
+		let (html, stylesheet) = HtmlRenderer::render_with_stylesheet(&screen, &options);
+		assert_eq!(html, "<div class=\"sv-terminal\"><span class=\"sv-fg-1\">A</span> B</div>");
+		assert!(
+			stylesheet
+				.ends_with("}}\n.sv-terminal{color:var(--sv-fg);background:var(--sv-bg);}\n.sv-fg-1{color:var(--sv-red);}\n")
+		);
+	}
+
+	#[test]
+	fn document_test() {
+		let options = HtmlOptions {
+			document: true,
+			font_family: String::from("monospace"),
+			..HtmlOptions::default()
+		};
+		let screen =
+			Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(b"\x1B]10;#eee\x07\x1B]11;#111\x07\x1B[5mA\x1B[0m B"));
+
+		assert_eq!(
+			HtmlRenderer::render_screen(&screen, &options),
+			String::from(
+				"<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n@keyframes blink{50%{opacity:0;}}\n</style>\n</head>\n<body style=\"margin:0;background:#111;\">\n<pre style=\"color:#eee;background:#111;font-family:monospace;margin:0;padding:1em;\"><span style=\"animation:blink 1s step-start infinite;\">A</span> B</pre>\n</body>\n</html>"
+			)
+		);
+	}
+
+	#[test]
+	fn email_mode_test() {
+		let options = HtmlOptions {
+			style_mode: StyleMode::Email,
+			..HtmlOptions::default()
+		};
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(
+			b"\x1B[1;31mFAILED\x1B[0m \x1B[2;5mslowly\x1B[0m \x1B[4mtests\x1B[0m \x1B[5mnow\x1B[0m \x1B[8msecret\x1B[0m",
+		));
+
+		assert_eq!(
+			HtmlRenderer::render_with_stylesheet(&screen, &options),
+			(
+				String::from(
+					"<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" border=\"0\" bgcolor=\"#ffffff\" style=\"background-color:#ffffff;\"><tr><td style=\"padding:16px;\"><pre style=\"margin:0;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,&#39;Liberation Mono&#39;,monospace;color:#000000;\"><font color=\"#000000\"><font color=\"#ff0000\" style=\"font-weight:bold;color:#ff0000;\">FAILED</font> <font color=\"#808080\" style=\"color:#808080;\">slowly</font> <span style=\"text-decoration:underline;\">tests</span> now       </font></pre></td></tr></table>"
+				),
+				String::new()
+			)
+		);
+
+		// Css variables don't apply and a document has no stylesheet
+		let options = HtmlOptions {
+			document: true,
+			css_variables: Some(CssVariables::default()),
+			..options
+		};
+		let mut screen = Screen::default().with_palette(Theme::DRACULA.palette());
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(b"\x1B]0;CI\x07\x1B[32mok"));
+		assert_eq!(
+			HtmlRenderer::render_screen(&screen, &options),
+			String::from(
+				"<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>CI</title>\n</head>\n<body style=\"margin:0;\">\n<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\" border=\"0\" bgcolor=\"#282a36\" style=\"background-color:#282a36;\"><tr><td style=\"padding:16px;\"><pre style=\"margin:0;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,&#39;Liberation Mono&#39;,monospace;color:#f8f8f2;\"><font color=\"#f8f8f2\"><font color=\"#50fa7b\" style=\"color:#50fa7b;\">ok</font></font></pre></td></tr></table>\n</body>\n</html>"
+			)
+		);
+	}
+
+	#[test]
+	fn non_sgr_sequences_ignored_test() {
+		assert_eq!(render(b"\x1B[?25lA\x1B]0;Title\x07B"), String::from("AB"));
+	}
+}
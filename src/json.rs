@@ -0,0 +1,363 @@
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JsonError {
+	pub line: usize,
+	pub message: String,
+}
+
+/// A JSON value. Parsing also allows the comments and trailing commas Windows Terminal uses in settings.json
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(String, Json)>),
+}
+
+impl Json {
+	pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+		match self {
+			Json::Object(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn parse(input: &str) -> Result<Self, JsonError> {
+		let mut parser = JsonParser {
+			chars: input.chars().collect(),
+			position: 0,
+			depth: 0,
+		};
+		let value = parser.value()?;
+		parser.skip_whitespace()?;
+		if parser.position < parser.chars.len() {
+			return Err(parser.error("unexpected content after the end of the document"));
+		}
+		Ok(value)
+	}
+}
+
+// Compact JSON without any whitespace
+impl std::fmt::Display for Json {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Json::Null => write!(f, "null"),
+			Json::Bool(value) => write!(f, "{value}"),
+			Json::Number(number) if number.is_finite() => write!(f, "{number}"),
+			Json::Number(_) => write!(f, "null"),
+			Json::String(string) => write_string(f, string),
+			Json::Array(values) => {
+				write!(f, "[")?;
+				for (index, value) in values.iter().enumerate() {
+					if index > 0 {
+						write!(f, ",")?;
+					}
+					write!(f, "{value}")?;
+				}
+				write!(f, "]")
+			},
+			Json::Object(entries) => {
+				write!(f, "{{")?;
+				for (index, (key, value)) in entries.iter().enumerate() {
+					if index > 0 {
+						write!(f, ",")?;
+					}
+					write_string(f, key)?;
+					write!(f, ":{value}")?;
+				}
+				write!(f, "}}")
+			},
+		}
+	}
+}
+
+fn write_string(f: &mut std::fmt::Formatter, string: &str) -> std::fmt::Result {
+	write!(f, "\"")?;
+	for ch in string.chars() {
+		match ch {
+			'"' => write!(f, "\\\"")?,
+			'\\' => write!(f, "\\\\")?,
+			'\n' => write!(f, "\\n")?,
+			'\r' => write!(f, "\\r")?,
+			'\t' => write!(f, "\\t")?,
+			'\u{0}'..='\u{1F}' | '\u{7F}' => write!(f, "\\u{:04x}", ch as u32)?,
+			_ => write!(f, "{ch}")?,
+		}
+	}
+	write!(f, "\"")
+}
+
+struct JsonParser {
+	chars: Vec<char>,
+	position: usize,
+	depth: usize,
+}
+
+impl JsonParser {
+	// Arrays and objects are parsed recursively so deeper documents are an error instead of a stack overflow
+	const MAX_DEPTH: usize = 128;
+
+	fn error(&self, message: &str) -> JsonError {
+		JsonError {
+			line: self.chars[..self.position.min(self.chars.len())].iter().filter(|c| **c == '\n').count() + 1,
+			message: message.to_string(),
+		}
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.position).copied()
+	}
+
+	fn skip_whitespace(&mut self) -> Result<(), JsonError> {
+		loop {
+			match (self.peek(), self.chars.get(self.position + 1)) {
+				(Some(c), _) if c.is_whitespace() => self.position += 1,
+				(Some('/'), Some('/')) => {
+					while self.peek().is_some_and(|c| c != '\n') {
+						self.position += 1;
+					}
+				},
+				(Some('/'), Some('*')) => {
+					self.position += 2;
+					while !(self.peek() == Some('*') && self.chars.get(self.position + 1) == Some(&'/')) {
+						if self.peek().is_none() {
+							return Err(self.error("unterminated comment"));
+						}
+						self.position += 1;
+					}
+					self.position += 2;
+				},
+				_ => return Ok(()),
+			}
+		}
+	}
+
+	fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+		self.skip_whitespace()?;
+		if self.peek() == Some(expected) {
+			self.position += 1;
+			Ok(())
+		} else {
+			Err(self.error(&format!("expected \"{expected}\"")))
+		}
+	}
+
+	fn value(&mut self) -> Result<Json, JsonError> {
+		if self.depth == Self::MAX_DEPTH {
+			return Err(self.error(&format!("nested deeper than {} levels", Self::MAX_DEPTH)));
+		}
+		self.depth += 1;
+		let value = self.nested_value();
+		self.depth -= 1;
+		value
+	}
+
+	fn nested_value(&mut self) -> Result<Json, JsonError> {
+		self.skip_whitespace()?;
+
+		match self.peek() {
+			Some('{') => {
+				self.position += 1;
+				let mut entries = Vec::new();
+				loop {
+					self.skip_whitespace()?;
+					if self.peek() == Some('}') {
+						self.position += 1;
+						return Ok(Json::Object(entries));
+					}
+					let key = self.string()?;
+					self.expect(':')?;
+					entries.push((key, self.value()?));
+					self.skip_whitespace()?;
+					match self.peek() {
+						Some(',') => self.position += 1,
+						Some('}') => {},
+						_ => return Err(self.error("expected \",\" or \"}\"")),
+					}
+				}
+			},
+			Some('[') => {
+				self.position += 1;
+				let mut items = Vec::new();
+				loop {
+					self.skip_whitespace()?;
+					if self.peek() == Some(']') {
+						self.position += 1;
+						return Ok(Json::Array(items));
+					}
+					items.push(self.value()?);
+					self.skip_whitespace()?;
+					match self.peek() {
+						Some(',') => self.position += 1,
+						Some(']') => {},
+						_ => return Err(self.error("expected \",\" or \"]\"")),
+					}
+				}
+			},
+			Some('"') => Ok(Json::String(self.string()?)),
+			Some('t') => self.literal("true", Json::Bool(true)),
+			Some('f') => self.literal("false", Json::Bool(false)),
+			Some('n') => self.literal("null", Json::Null),
+			Some(c) if c == '-' || c.is_ascii_digit() => {
+				let start = self.position;
+				while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+					self.position += 1;
+				}
+				let number = self.chars[start..self.position].iter().collect::<String>();
+				number.parse::<f64>().map(Json::Number).map_err(|_| self.error(&format!("invalid number {number}")))
+			},
+			Some(c) => Err(self.error(&format!("unexpected \"{c}\""))),
+			None => Err(self.error("unexpected end of file")),
+		}
+	}
+
+	fn literal(&mut self, literal: &str, value: Json) -> Result<Json, JsonError> {
+		let end = self.position + literal.len();
+		if self.chars.get(self.position..end).is_some_and(|chars| chars.iter().copied().eq(literal.chars())) {
+			self.position = end;
+			Ok(value)
+		} else {
+			Err(self.error("invalid literal"))
+		}
+	}
+
+	fn string(&mut self) -> Result<String, JsonError> {
+		self.skip_whitespace()?;
+		if self.peek() != Some('"') {
+			return Err(self.error("expected a string"));
+		}
+		self.position += 1;
+
+		let mut string = String::new();
+		loop {
+			match self.peek() {
+				Some('"') => {
+					self.position += 1;
+					return Ok(string);
+				},
+				Some('\\') => {
+					self.position += 1;
+					let escaped = match self.peek() {
+						Some('n') => '\n',
+						Some('t') => '\t',
+						Some('r') => '\r',
+						Some('b') => '\u{8}',
+						Some('f') => '\u{c}',
+						Some('u') => {
+							let code = self.hex_code(self.position + 1);
+							self.position += 4;
+							// Characters outside the basic multilingual plane are escaped as a UTF-16 surrogate pair
+							if let Some(high @ 0xD800..=0xDBFF) = code
+								&& self.chars.get(self.position + 1..self.position + 3) == Some(&['\\', 'u'])
+								&& let Some(low @ 0xDC00..=0xDFFF) = self.hex_code(self.position + 3)
+							{
+								self.position += 6;
+								char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+							} else {
+								code.and_then(char::from_u32)
+							}
+							.unwrap_or(char::REPLACEMENT_CHARACTER)
+						},
+						Some(c) => c,
+						None => return Err(self.error("unterminated string")),
+					};
+					string.push(escaped);
+					self.position += 1;
+				},
+				Some(c) => {
+					string.push(c);
+					self.position += 1;
+				},
+				None => return Err(self.error("unterminated string")),
+			}
+		}
+	}
+
+	// The four hex digits of a \u escape
+	fn hex_code(&self, start: usize) -> Option<u32> {
+		let hex = self.chars.get(start..start + 4)?.iter().collect::<String>();
+		u32::from_str_radix(&hex, 16).ok()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parse_test() {
+		assert_eq!(
+			Json::parse(r#"{ "a": [1, -2.5e1, true, null], "b": "x\"\u00e9\n" }"#),
+			Ok(Json::Object(vec![
+				(String::from("a"), Json::Array(vec![Json::Number(1.0), Json::Number(-25.0), Json::Bool(true), Json::Null])),
+				(String::from("b"), Json::String(String::from("x\"é\n"))),
+			]))
+		);
+		assert_eq!(
+			Json::parse("// comment\n[1, /* two */ 2,]"),
+			Ok(Json::Array(vec![Json::Number(1.0), Json::Number(2.0)]))
+		);
+	}
+
+	#[test]
+	fn surrogate_pair_test() {
+		assert_eq!(Json::parse(r#""\ud83e\udd80 \uD83E\uDD80""#), Ok(Json::String(String::from("\u{1F980} \u{1F980}"))));
+		// Lone surrogates aren't characters
+		assert_eq!(Json::parse(r#""\ud83e \udd80\ud83e""#), Ok(Json::String(String::from("\u{FFFD} \u{FFFD}\u{FFFD}"))));
+		assert_eq!(Json::parse(r#""\ud83e\u0041""#), Ok(Json::String(String::from("\u{FFFD}A"))));
+	}
+
+	#[test]
+	fn parse_errors_test() {
+		assert_eq!(
+			Json::parse("[1,\n2"),
+			Err(JsonError {
+				line: 2,
+				message: String::from("expected \",\" or \"]\""),
+			})
+		);
+		assert_eq!(
+			Json::parse("[1] 2"),
+			Err(JsonError {
+				line: 1,
+				message: String::from("unexpected content after the end of the document"),
+			})
+		);
+		assert_eq!(
+			Json::parse("\"open"),
+			Err(JsonError {
+				line: 1,
+				message: String::from("unterminated string"),
+			})
+		);
+
+		let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+		assert!(Json::parse(&nested(128)).is_ok());
+		assert_eq!(
+			Json::parse(&nested(129)),
+			Err(JsonError {
+				line: 1,
+				message: String::from("nested deeper than 128 levels"),
+			})
+		);
+		assert_eq!(Json::parse(&"[".repeat(200_000)).unwrap_err().message, "nested deeper than 128 levels");
+		assert_eq!(Json::parse(&"{\"a\":".repeat(200)).unwrap_err().message, "nested deeper than 128 levels");
+	}
+
+	#[test]
+	fn to_string_test() {
+		let value = Json::Object(vec![
+			(String::from("text"), Json::String(String::from("a \"quote\"\\\n\x1B"))),
+			(String::from("numbers"), Json::Array(vec![Json::Number(1.0), Json::Number(-0.5), Json::Number(f64::NAN)])),
+			(String::from("flags"), Json::Array(vec![Json::Bool(true), Json::Null])),
+			(String::from("empty"), Json::Object(Vec::new())),
+		]);
+
+		assert_eq!(
+			value.to_string(),
+			r#"{"text":"a \"quote\"\\\n\u001b","numbers":[1,-0.5,null],"flags":[true,null],"empty":{}}"#
+		);
+		assert_eq!(Json::parse(&value.to_string()).unwrap().get("text"), value.get("text"));
+	}
+}
@@ -0,0 +1,334 @@
+use crate::{
+	palette::{Palette, Rgb},
+	screen::Screen,
+	styles::StyleNode,
+	themes::Theme,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LatexEnvironment {
+	/// A fancyvrb Verbatim block with \, { and } as command characters
+	#[default]
+	Verbatim,
+	/// A listings lstlisting block with styled text in escapes
+	Listings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LatexOptions {
+	pub environment: LatexEnvironment,
+}
+
+// What of a style LaTeX can show, with colors already resolved against the palette
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LatexStyle {
+	foreground: Option<Rgb>,
+	background: Option<Rgb>,
+	bold: bool,
+	italic: bool,
+	underline: bool,
+	hidden: bool,
+}
+
+impl LatexStyle {
+	fn is_plain(&self) -> bool {
+		self.foreground.is_none() && self.background.is_none() && !self.bold && !self.italic && !self.underline
+	}
+}
+
+/// A LaTeX block of the output colored with xcolor.
+/// Strikethrough, blink and the other styles without a command in plain LaTeX are dropped.
+pub struct LatexRenderer<'a> {
+	latex: String,
+	options: &'a LatexOptions,
+	palette: Palette,
+	// Every color used in the order it first appeared
+	colors: Vec<Rgb>,
+	// The delimiters of escapes to LaTeX in a listing
+	escape: Option<(&'static str, &'static str)>,
+}
+
+impl<'a> LatexRenderer<'a> {
+	pub const PACKAGES: &'static str =
+		"% Needs \\usepackage{xcolor} and \\usepackage{fancyvrb} or \\usepackage{listings}\n";
+	// Pairs for the escapeinside option of listings, the first one that doesn't show up in the output is used
+	const ESCAPES: [(&'static str, &'static str); 5] =
+		[("(*", "*)"), ("(@", "@)"), ("(!", "!)"), ("(|", "|)"), ("(^", "^)")];
+
+	/// Returns the color definitions followed by the block
+	pub fn render_screen(screen: &Screen, options: &'a LatexOptions) -> String {
+		let (block, preamble) = Self::render_with_preamble(screen, options);
+		preamble + &block
+	}
+
+	/// Returns the block and the \definecolor lines it needs, which can go in the preamble of the document
+	pub fn render_with_preamble(screen: &Screen, options: &'a LatexOptions) -> (String, String) {
+		let mut lines = screen.lines().collect::<Vec<_>>();
+		while lines.last().is_some_and(|line| line.is_empty()) {
+			lines.pop();
+		}
+
+		// Paper has no page colors to inherit so unknown default colors fall back to xterm's black on white
+		let mut palette = screen.palette().clone();
+		let default_foreground = palette.foreground;
+		palette.foreground = palette.foreground.or(Some(Theme::XTERM.foreground));
+		palette.background = palette.background.or(Some(Theme::XTERM.background));
+
+		let escape = match options.environment {
+			LatexEnvironment::Verbatim => None,
+			LatexEnvironment::Listings => {
+				let text = lines.iter().flat_map(|line| line.iter().flatten().map(|cell| cell.ch)).collect::<String>();
+				Self::ESCAPES.into_iter().find(|(open, close)| !text.contains(open) && !text.contains(close))
+			},
+		};
+		let mut renderer = Self {
+			latex: String::new(),
+			options,
+			palette,
+			colors: Vec::new(),
+			escape,
+		};
+
+		for line in &lines {
+			let styles = line
+				.iter()
+				.map(|cell| cell.map_or((' ', StyleNode::default()), |cell| (cell.ch, cell.style)))
+				.map(|(ch, style)| (ch, renderer.latex_style(&style)))
+				.collect::<Vec<_>>();
+			renderer.push_line(styles);
+			renderer.latex.push('\n');
+		}
+
+		let default_color = default_foreground.map(|color| renderer.color_name(color));
+		let mut block = match options.environment {
+			LatexEnvironment::Verbatim => {
+				let mut block = String::from("\\begin{Verbatim}[commandchars=\\\\\\{\\}");
+				if let Some(color) = &default_color {
+					block.push_str(&format!(",formatcom=\\color{{{color}}}"));
+				}
+				block
+			},
+			LatexEnvironment::Listings => {
+				let mut block = String::from("\\begin{lstlisting}[basicstyle=\\ttfamily");
+				if let Some(color) = &default_color {
+					block.push_str(&format!("\\color{{{color}}}"));
+				}
+				if let Some((open, close)) = renderer.escape {
+					block.push_str(&format!(",escapeinside={{{open}}}{{{close}}}"));
+				}
+				block
+			},
+		};
+		block.push_str("]\n");
+		block.push_str(&renderer.latex);
+		block.push_str(match options.environment {
+			LatexEnvironment::Verbatim => "\\end{Verbatim}\n",
+			LatexEnvironment::Listings => "\\end{lstlisting}\n",
+		});
+
+		let mut preamble = String::from(Self::PACKAGES);
+		for color in &renderer.colors {
+			preamble.push_str(&format!(
+				"\\definecolor{{{}}}{{RGB}}{{{},{},{}}}\n",
+				Self::name(*color),
+				color.r,
+				color.g,
+				color.b
+			));
+		}
+
+		(block, preamble)
+	}
+
+	fn latex_style(&self, style: &StyleNode) -> LatexStyle {
+		let foreground = self.palette.foreground.unwrap_or(Theme::XTERM.foreground);
+		let background = self.palette.background.unwrap_or(Theme::XTERM.background);
+		let (style_foreground, style_background) = style.resolved_colors(&self.palette);
+
+		// Dim text is drawn halfway between its color and the background like the opacity of the html output
+		let mut text = style_foreground.unwrap_or(foreground);
+		let fill = style_background.unwrap_or(background);
+		if style.dim() {
+			text = text.blend(fill);
+		}
+
+		LatexStyle {
+			foreground: (text != foreground).then_some(text),
+			background: (fill != background).then_some(fill),
+			bold: style.bold(),
+			italic: style.italic(),
+			underline: style.underline().is_some(),
+			hidden: style.hidden(),
+		}
+	}
+
+	// Commands can't span lines in either environment so every line opens and closes its own
+	fn push_line(&mut self, cells: Vec<(char, LatexStyle)>) {
+		let mut runs: Vec<(LatexStyle, String)> = Vec::new();
+		for (ch, style) in cells {
+			let ch = if style.hidden { ' ' } else { ch };
+			match runs.last_mut() {
+				Some((last, text)) if *last == style => text.push(ch),
+				_ => runs.push((style, ch.to_string())),
+			}
+		}
+
+		for (style, text) in runs {
+			if style.is_plain() {
+				self.push_text(&text, false);
+				continue;
+			}
+			if self.options.environment == LatexEnvironment::Listings && self.escape.is_none() {
+				// Every escape delimiter shows up in the text so the styles can't be written
+				self.latex.push_str(&text);
+				continue;
+			}
+
+			let mut commands = Vec::new();
+			if let Some(color) = style.background {
+				commands.push(format!("\\colorbox{{{}}}", self.color_name(color)));
+			}
+			if let Some(color) = style.foreground {
+				commands.push(format!("\\textcolor{{{}}}", self.color_name(color)));
+			}
+			if style.bold {
+				commands.push(String::from("\\textbf"));
+			}
+			if style.italic {
+				commands.push(String::from("\\textit"));
+			}
+			if style.underline {
+				commands.push(String::from("\\underline"));
+			}
+
+			if let Some((open, _)) = self.escape {
+				self.latex.push_str(open);
+			}
+			// A colorbox pads its text unless fboxsep is zero, set in a group to keep it from leaking out
+			if style.background.is_some() {
+				self.latex.push_str("{\\fboxsep=0pt");
+			}
+			for command in &commands {
+				self.latex.push_str(command);
+				self.latex.push('{');
+			}
+			self.push_text(&text, true);
+			self.latex.push_str(&"}".repeat(commands.len() + style.background.is_some() as usize));
+			if let Some((_, close)) = self.escape {
+				self.latex.push_str(close);
+			}
+		}
+	}
+
+	// Text in a Verbatim block only needs the command characters escaped while text in a listings escape
+	// is regular LaTeX where all special characters need escaping and spaces would collapse
+	fn push_text(&mut self, text: &str, styled: bool) {
+		let escaped = styled && self.escape.is_some();
+
+		for ch in text.chars() {
+			match ch {
+				'\\' if escaped => self.latex.push_str("\\textbackslash{}"),
+				'{' | '}' | '#' | '$' | '%' | '&' | '_' if escaped => {
+					self.latex.push('\\');
+					self.latex.push(ch);
+				},
+				'~' if escaped => self.latex.push_str("\\textasciitilde{}"),
+				'^' if escaped => self.latex.push_str("\\textasciicircum{}"),
+				' ' if escaped => self.latex.push_str("\\ "),
+				'\\' | '{' | '}' if self.options.environment == LatexEnvironment::Verbatim => {
+					self.latex.push_str(&format!("\\char{}{{}}", ch as u32));
+				},
+				_ => self.latex.push(ch),
+			}
+		}
+	}
+
+	fn color_name(&mut self, color: Rgb) -> String {
+		if !self.colors.contains(&color) {
+			self.colors.push(color);
+		}
+		Self::name(color)
+	}
+
+	fn name(color: Rgb) -> String {
+		format!("sv-{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8], options: &LatexOptions) -> (String, String) {
+		let mut screen = Screen::default();
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(input));
+		LatexRenderer::render_with_preamble(&screen, options)
+	}
+
+	#[test]
+	fn verbatim_test() {
+		let options = LatexOptions::default();
+
+		assert_eq!(
+			render(b"\x1B[1;31mError:\x1B[0m 100% {done}\n\x1B[3;4mmore\x1B[0m\n", &options),
+			(
+				String::from(
+					"\\begin{Verbatim}[commandchars=\\\\\\{\\}]\n\\textcolor{sv-ff0000}{\\textbf{Error:}} 100% \\char123{}done\\char125{}\n\\textit{\\underline{more}}\n\\end{Verbatim}\n"
+				),
+				format!("{}\\definecolor{{sv-ff0000}}{{RGB}}{{255,0,0}}\n", LatexRenderer::PACKAGES)
+			)
+		);
+		assert_eq!(
+			render(b"a\\b", &options).0,
+			"\\begin{Verbatim}[commandchars=\\\\\\{\\}]\na\\char92{}b\n\\end{Verbatim}\n"
+		);
+	}
+
+	#[test]
+	fn colors_test() {
+		let options = LatexOptions::default();
+
+		// Reverse video swaps in the default colors and dim text is mixed with the background
+		let (block, preamble) = render(b"\x1B[7mrev\x1B[0m \x1B[2mdim\x1B[0m \x1B[8mhidden\x1B[0m", &options);
+		assert_eq!(
+			block,
+			"\\begin{Verbatim}[commandchars=\\\\\\{\\}]\n{\\fboxsep=0pt\\colorbox{sv-000000}{\\textcolor{sv-ffffff}{rev}}} \\textcolor{sv-808080}{dim}       \n\\end{Verbatim}\n"
+		);
+		assert_eq!(
+			preamble,
+			format!(
+				"{}\\definecolor{{sv-000000}}{{RGB}}{{0,0,0}}\n\\definecolor{{sv-ffffff}}{{RGB}}{{255,255,255}}\n\\definecolor{{sv-808080}}{{RGB}}{{128,128,128}}\n",
+				LatexRenderer::PACKAGES
+			)
+		);
+
+		// A known default foreground colors the whole block
+		let mut screen = Screen::default().with_palette(Theme::DRACULA.palette());
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(b"text"));
+		assert_eq!(
+			LatexRenderer::render_screen(&screen, &options),
+			format!(
+				"{}\\definecolor{{sv-f8f8f2}}{{RGB}}{{248,248,242}}\n\\begin{{Verbatim}}[commandchars=\\\\\\{{\\}},formatcom=\\color{{sv-f8f8f2}}]\ntext\n\\end{{Verbatim}}\n",
+				LatexRenderer::PACKAGES
+			)
+		);
+	}
+
+	#[test]
+	fn listings_test() {
+		let options = LatexOptions {
+			environment: LatexEnvironment::Listings,
+		};
+
+		assert_eq!(
+			render(b"$ \x1B[32mok 50% a_b ~^\\{}\x1B[0m {plain}\n", &options).0,
+			"\\begin{lstlisting}[basicstyle=\\ttfamily,escapeinside={(*}{*)}]\n$ (*\\textcolor{sv-00cd00}{ok\\ 50\\%\\ a\\_b\\ \\textasciitilde{}\\textasciicircum{}\\textbackslash{}\\{\\}}*) {plain}\n\\end{lstlisting}\n"
+		);
+		// Delimiters that show up in the output are skipped
+		assert_eq!(
+			render(b"(* \x1B[1m(@x\x1B[0m", &options).0,
+			"\\begin{lstlisting}[basicstyle=\\ttfamily,escapeinside={(!}{!)}]\n(* (!\\textbf{(@x}!)\n\\end{lstlisting}\n"
+		);
+	}
+}
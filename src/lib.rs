@@ -0,0 +1,19 @@
+pub mod ansi;
+pub mod asciicast;
+pub mod ast;
+pub mod html;
+mod json;
+pub mod latex;
+pub mod links;
+pub mod markdown;
+pub mod nodes;
+pub mod palette;
+pub mod player;
+pub mod rtf;
+pub mod screen;
+pub mod shellvetica;
+pub mod styles;
+pub mod svg;
+pub mod text;
+pub mod theme_files;
+pub mod themes;
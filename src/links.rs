@@ -0,0 +1,129 @@
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RejectedLink {
+	/// Keep the linked text but drop the link
+	#[default]
+	Drop,
+	/// Keep the linked text and show the url next to it as plain text
+	Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkPolicy {
+	pub schemes: Vec<String>,
+	pub rel: Option<String>,
+	pub target: Option<String>,
+	/// Rewrites file://host/path to <file_base_url>/path
+	pub file_base_url: Option<String>,
+	pub rejected: RejectedLink,
+}
+
+impl Default for LinkPolicy {
+	fn default() -> Self {
+		Self {
+			schemes: Self::DEFAULT_SCHEMES.iter().map(|scheme| scheme.to_string()).collect(),
+			rel: None,
+			target: None,
+			file_base_url: None,
+			rejected: RejectedLink::default(),
+		}
+	}
+}
+
+impl LinkPolicy {
+	pub const DEFAULT_SCHEMES: [&str; 4] = ["http", "https", "file", "mailto"];
+	pub const NOFOLLOW: &str = "noopener nofollow";
+
+	/// Returns the url to link to or None if the url is not allowed
+	pub fn resolve(&self, url: &str) -> Option<String> {
+		// Browsers skip whitespace and control characters inside urls so "java\tscript:" would still run
+		if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+			return None;
+		}
+
+		let scheme = Self::scheme(url)?.to_ascii_lowercase();
+		if !self.schemes.iter().any(|allowed| allowed.eq_ignore_ascii_case(&scheme)) {
+			return None;
+		}
+
+		if scheme == "file"
+			&& let Some(base_url) = &self.file_base_url
+		{
+			let path = &url[scheme.len() + 1..];
+			let path = match path.strip_prefix("//") {
+				Some(host_and_path) => host_and_path.find('/').map_or("", |index| &host_and_path[index..]),
+				None => path,
+			};
+			return Some(format!("{}/{}", base_url.trim_end_matches('/'), path.trim_start_matches('/')));
+		}
+
+		Some(url.to_string())
+	}
+
+	fn scheme(url: &str) -> Option<&str> {
+		let (scheme, _) = url.split_once(':')?;
+		let mut chars = scheme.chars();
+
+		if chars.next()?.is_ascii_alphabetic() && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')) {
+			Some(scheme)
+		} else {
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn allowed_schemes_test() {
+		let policy = LinkPolicy::default();
+		assert_eq!(policy.resolve("https://example.com"), Some(String::from("https://example.com")));
+		assert_eq!(policy.resolve("HTTP://example.com"), Some(String::from("HTTP://example.com")));
+		assert_eq!(policy.resolve("mailto:hi@example.com"), Some(String::from("mailto:hi@example.com")));
+		assert_eq!(policy.resolve("file:///etc/hosts"), Some(String::from("file:///etc/hosts")));
+	}
+
+	#[test]
+	fn rejected_schemes_test() {
+		let policy = LinkPolicy::default();
+		assert_eq!(policy.resolve("javascript:alert(1)"), None);
+		assert_eq!(policy.resolve("JavaScript:alert(1)"), None);
+		assert_eq!(policy.resolve("java\tscript:alert(1)"), None);
+		assert_eq!(policy.resolve(" javascript:alert(1)"), None);
+		assert_eq!(policy.resolve("data:text/html;base64,PHNjcmlwdD4="), None);
+		assert_eq!(policy.resolve("vbscript:msgbox"), None);
+		assert_eq!(policy.resolve("//example.com"), None);
+		assert_eq!(policy.resolve("relative/path"), None);
+		assert_eq!(policy.resolve(""), None);
+	}
+
+	#[test]
+	fn custom_schemes_test() {
+		let policy = LinkPolicy {
+			schemes: vec![String::from("https")],
+			..LinkPolicy::default()
+		};
+		assert_eq!(policy.resolve("https://example.com"), Some(String::from("https://example.com")));
+		assert_eq!(policy.resolve("http://example.com"), None);
+		assert_eq!(policy.resolve("file:///etc/hosts"), None);
+	}
+
+	#[test]
+	fn file_base_url_test() {
+		let policy = LinkPolicy {
+			file_base_url: Some(String::from("https://files.example.com/browse/")),
+			..LinkPolicy::default()
+		};
+		assert_eq!(
+			policy.resolve("file://build-host/home/ci/src/main.rs"),
+			Some(String::from("https://files.example.com/browse/home/ci/src/main.rs"))
+		);
+		assert_eq!(
+			policy.resolve("file:///home/ci/src/main.rs"),
+			Some(String::from("https://files.example.com/browse/home/ci/src/main.rs"))
+		);
+		assert_eq!(policy.resolve("file://host"), Some(String::from("https://files.example.com/browse/")));
+		assert_eq!(policy.resolve("https://example.com"), Some(String::from("https://example.com")));
+	}
+}
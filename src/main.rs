@@ -1,19 +1,132 @@
 use std::io::{Read, stdin};
 
-mod nodes;
-mod shellvetica;
-mod styles;
+use shellvetica::{
+	ansi::AnsiRenderer, asciicast::Asciicast, ast::JsonRenderer, html::HtmlRenderer, latex::LatexRenderer,
+	markdown::MarkdownRenderer, nodes::TerminalOutputParser, player::HtmlPlayer, rtf::RtfRenderer, screen::Screen,
+	svg::SvgRenderer, text::TextRenderer, theme_files::ThemeFormat,
+};
 
-use crate::shellvetica::Shellvetica;
+mod cli;
+
+use crate::cli::{Cli, Format, HELP};
 
 fn main() {
+	let cli = match Cli::parse(std::env::args().skip(1)) {
+		Ok(cli) => cli,
+		Err(error) => {
+			eprintln!("{error}\n\n{HELP}");
+			std::process::exit(1);
+		},
+	};
+
+	if cli.help {
+		print!("{HELP}");
+		return;
+	}
+
 	let mut buffer = Vec::new();
 	match stdin().read_to_end(&mut buffer) {
 		Ok(_) => {},
 		Err(error) => panic!("Failed to read buffer: {error:?}"),
 	}
-	let input = String::from_utf8_lossy(&buffer);
 
-	let html = Shellvetica::convert(&input).export();
-	println!("{html}");
+	let mut palette = cli.theme.map(|theme| theme.palette());
+	if let Some(path) = &cli.theme_file {
+		match ThemeFormat::load(path, cli.theme_scheme.as_deref()) {
+			Ok(theme_palette) => palette = Some(theme_palette),
+			Err(error) => {
+				eprintln!("{}: {error}", path.display());
+				std::process::exit(1);
+			},
+		}
+	}
+
+	let (screen, nodes) = if cli.asciicast {
+		let cast = match Asciicast::parse(&String::from_utf8_lossy(&buffer)) {
+			Ok(cast) => cast,
+			Err(error) => {
+				eprintln!("{error}");
+				std::process::exit(1);
+			},
+		};
+		// A theme given on the command line wins over the one recorded in the file
+		let palette = palette.or_else(|| cast.palette.clone()).unwrap_or_default();
+		match cli.format {
+			Format::Html | Format::Svg => {
+				let mut animation = cast.animation(palette);
+				for frame in &mut animation.frames {
+					frame.screen.reduce_colors(cli.color_depth);
+				}
+				if cli.format == Format::Html {
+					print_output(&HtmlPlayer::render(&animation, &cli.html));
+				} else {
+					print_output(&SvgRenderer::render_animation(&animation, &cli.svg));
+				}
+				return;
+			},
+			// Formats without animation get everything that was printed by the end of the recording, with the
+			// nodes being everything it wrote to the terminal in one go
+			_ => {
+				let output = cast.events.iter().map(|(_, data)| data.as_str()).collect::<String>();
+				let nodes = TerminalOutputParser::parse_to_nodes(output.as_bytes());
+				let mut screen = Screen::new(Some(cast.width), Some(cast.height))
+					.with_palette(palette)
+					.with_carriage_return(cli.carriage_return);
+				screen.apply_all(&nodes);
+				screen.reduce_colors(cli.color_depth);
+				(screen, nodes)
+			},
+		}
+	} else {
+		let mut screen = Screen::default().with_carriage_return(cli.carriage_return);
+		if let Some(palette) = palette {
+			screen = screen.with_palette(palette);
+		}
+		let nodes = TerminalOutputParser::parse_to_nodes(&buffer);
+		screen.apply_all(&nodes);
+		screen.reduce_colors(cli.color_depth);
+		(screen, nodes)
+	};
+
+	let output = match cli.format {
+		Format::Html => {
+			let (html, stylesheet) = HtmlRenderer::render_with_stylesheet(&screen, &cli.html);
+			// Inline styles leave the stylesheet empty and the html doesn't link it
+			if let Some(path) = &cli.stylesheet
+				&& !stylesheet.is_empty()
+				&& let Err(error) = std::fs::write(path, stylesheet)
+			{
+				eprintln!("Failed to write stylesheet to {}: {error}", path.display());
+				std::process::exit(1);
+			}
+			html
+		},
+		Format::Svg => SvgRenderer::render_screen(&screen, &cli.svg),
+		Format::Text => TextRenderer::render_screen(&screen, &cli.text),
+		Format::Markdown => MarkdownRenderer::render_screen(&screen, &cli.markdown),
+		Format::Latex => match &cli.preamble {
+			Some(path) => {
+				let (block, preamble) = LatexRenderer::render_with_preamble(&screen, &cli.latex);
+				if let Err(error) = std::fs::write(path, preamble) {
+					eprintln!("Failed to write preamble to {}: {error}", path.display());
+					std::process::exit(1);
+				}
+				block
+			},
+			None => LatexRenderer::render_screen(&screen, &cli.latex),
+		},
+		Format::Rtf => RtfRenderer::render_screen(&screen, &cli.rtf),
+		Format::Json => JsonRenderer::render(&nodes, &screen, &cli.json),
+		Format::Ansi => AnsiRenderer::render_screen(&screen),
+	};
+	print_output(&output);
+}
+
+// Text keeps the final newline of the input so only output without one gets a newline added
+fn print_output(output: &str) {
+	if output.ends_with('\n') {
+		print!("{output}");
+	} else {
+		println!("{output}");
+	}
 }
@@ -0,0 +1,315 @@
+use crate::{screen::Screen, styles::StyleNode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MarkdownMode {
+	/// Paragraphs with emphasis for lines that read like prose and code blocks for lines whose spacing matters
+	#[default]
+	Auto,
+	/// Everything in a single code block
+	Code,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MarkdownOptions {
+	pub mode: MarkdownMode,
+	/// Write code blocks with styles as ```ansi blocks that keep the escape sequences, which GitHub and Discord color
+	pub ansi: bool,
+}
+
+/// GitHub flavored Markdown for pasting output into issues and comments
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+	// Escaped everywhere since they start emphasis, code, links, html, tables or entities
+	const SPECIAL: &str = "\\`*_[]<>~|#&";
+
+	pub fn render_screen(screen: &Screen, options: &MarkdownOptions) -> String {
+		// Hidden text is left out everywhere, even in ansi blocks where it would only be hidden by the viewer
+		let mut lines = screen
+			.lines()
+			.map(|line| {
+				line.iter().map(|cell| {
+					cell.map_or((' ', StyleNode::default()), |cell| (if cell.style.hidden() { ' ' } else { cell.ch }, cell.style))
+				})
+			})
+			.map(|line| line.collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		while lines.last().is_some_and(|line| Self::is_blank(line)) {
+			lines.pop();
+		}
+		if lines.is_empty() {
+			return String::new();
+		}
+
+		if options.mode == MarkdownMode::Code {
+			return Self::code_block(&lines, options) + "\n";
+		}
+
+		let mut blocks = Vec::new();
+		let mut row = 0;
+		while row < lines.len() {
+			if Self::is_blank(&lines[row]) {
+				row += 1;
+			} else if Self::is_monospace(&lines[row]) {
+				// Blank lines stay in the block as long as more monospace lines follow them
+				let mut end = row + 1;
+				while let Some(next) = lines[end..].iter().position(|line| !Self::is_blank(line))
+					&& Self::is_monospace(&lines[end + next])
+				{
+					end += next + 1;
+				}
+				blocks.push(Self::code_block(&lines[row..end], options));
+				row = end;
+			} else {
+				let end = lines[row..]
+					.iter()
+					.position(|line| Self::is_blank(line) || Self::is_monospace(line))
+					.map_or(lines.len(), |offset| row + offset);
+				let paragraph = lines[row..end].iter().map(|line| Self::prose_line(line)).collect::<Vec<_>>();
+				// A backslash at the end of a line is a hard line break
+				blocks.push(paragraph.join("\\\n"));
+				row = end;
+			}
+		}
+
+		blocks.join("\n\n") + "\n"
+	}
+
+	fn is_blank(line: &[(char, StyleNode)]) -> bool {
+		line.iter().all(|(ch, _)| ch.is_whitespace())
+	}
+
+	// Indentation, runs of spaces for alignment, tabs and box drawing only survive in a code block
+	fn is_monospace(line: &[(char, StyleNode)]) -> bool {
+		let text = line.iter().map(|(ch, _)| ch).collect::<String>();
+		let text = text.trim_end();
+		text.starts_with(char::is_whitespace)
+			|| text.contains("  ")
+			|| text.contains('\t')
+			|| text.contains(|ch| ('\u{2500}'..='\u{259F}').contains(&ch))
+	}
+
+	fn code_block(lines: &[Vec<(char, StyleNode)>], options: &MarkdownOptions) -> String {
+		let ansi = options.ansi && lines.iter().flatten().any(|(_, style)| !style.is_default());
+
+		let mut content = Vec::new();
+		for line in lines {
+			let mut text = String::new();
+			let mut current = StyleNode::default();
+			for (ch, style) in line {
+				if ansi && style.effective() != current.effective() {
+					text.push_str(&style.to_sgr());
+					current = *style;
+				}
+				text.push(*ch);
+			}
+			if !current.is_default() {
+				text.push_str("\x1B[0m");
+			}
+			content.push(text.trim_end().to_string());
+		}
+		let content = content.join("\n");
+
+		// The fence has to be longer than any run of backticks in the output
+		let longest = content.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+		let fence = "`".repeat(longest.max(2) + 1);
+		let info = if ansi { "ansi" } else { "" };
+		format!("{fence}{info}\n{content}\n{fence}")
+	}
+
+	fn prose_line(line: &[(char, StyleNode)]) -> String {
+		let len = line.iter().rposition(|(ch, _)| !ch.is_whitespace()).map_or(0, |col| col + 1);
+		let line = &line[..len];
+
+		// Runs of text with the same emphasis while colors and other styles are dropped
+		let mut runs: Vec<((bool, bool, bool), String)> = Vec::new();
+		for (ch, style) in line {
+			let emphasis = (style.strikethrough(), style.bold(), style.italic());
+			match runs.last_mut() {
+				Some((last, text)) if *last == emphasis => text.push(*ch),
+				_ => runs.push((emphasis, ch.to_string())),
+			}
+		}
+
+		let mut markdown = String::new();
+		let mut closed_emphasis = false;
+		for (index, ((strike, bold, italic), text)) in runs.iter().enumerate() {
+			let escaped = Self::escape(text, index == 0);
+			let core = escaped.trim();
+			let lead = &escaped[..escaped.len() - escaped.trim_start().len()];
+			let trail = &escaped[escaped.trim_end().len()..];
+
+			let previous = markdown.chars().chain(lead.chars()).last();
+			let next = runs.get(index + 1).and_then(|(_, text)| text.chars().next());
+			let touches_word = |edge: Option<char>, outer: Option<char>| {
+				edge.is_some_and(|ch| ch.is_ascii_punctuation()) && outer.is_some_and(char::is_alphanumeric)
+			};
+			// Markers right after other markers or between punctuation and a word don't parse as emphasis
+			let unsafe_markers = core.is_empty()
+				|| (closed_emphasis && lead.is_empty())
+				|| touches_word(core.chars().next(), previous)
+				|| touches_word(core.chars().last(), if trail.is_empty() { next } else { None });
+
+			let open = [(*strike, "~~"), (*bold, "**"), (*italic, "*")]
+				.iter()
+				.filter(|(set, _)| *set)
+				.map(|(_, marker)| *marker)
+				.collect::<String>();
+
+			if !unsafe_markers && !open.is_empty() {
+				markdown.push_str(lead);
+				markdown.push_str(&open);
+				markdown.push_str(core);
+				markdown.extend(open.chars().rev());
+				markdown.push_str(trail);
+				closed_emphasis = trail.is_empty();
+			} else {
+				markdown.push_str(&escaped);
+				closed_emphasis = false;
+			}
+		}
+
+		markdown
+	}
+
+	fn escape(text: &str, line_start: bool) -> String {
+		let mut escaped = String::with_capacity(text.len());
+		// Block markers like lists and setext underlines only count at the start of a line
+		let list_number =
+			text.find(|ch: char| !ch.is_ascii_digit()).filter(|end| *end > 0 && text[*end..].starts_with(['.', ')']));
+
+		for (index, ch) in text.char_indices() {
+			let block_marker = line_start && ((index == 0 && "+-=".contains(ch)) || list_number == Some(index));
+			if Self::SPECIAL.contains(ch) || block_marker {
+				escaped.push('\\');
+			}
+			escaped.push(ch);
+		}
+
+		escaped
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8], options: &MarkdownOptions) -> String {
+		let mut screen = Screen::default();
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(input));
+		MarkdownRenderer::render_screen(&screen, options)
+	}
+
+	#[test]
+	fn prose_test() {
+		let options = MarkdownOptions::default();
+
+		assert_eq!(render(b"", &options), "");
+		assert_eq!(
+			render(b"\x1B[1;31mError:\x1B[0m build \x1B[3mfailed\x1B[0m\nsee \x1B[9mlog\x1B[0m\n\nnext\n", &options),
+			"**Error:** build *failed*\\\nsee ~~log~~\n\nnext\n"
+		);
+		assert_eq!(render(b"\x1B[1;3mboth\x1B[0m", &options), "***both***\n");
+		// Surrounding whitespace moves outside of the markers
+		assert_eq!(render(b"a\x1B[1m b \x1B[0mc", &options), "a **b** c\n");
+		// Styles that Markdown can't show are dropped
+		assert_eq!(render(b"\x1B[4;32mgreen\x1B[0m", &options), "green\n");
+	}
+
+	#[test]
+	fn unsafe_emphasis_test() {
+		let options = MarkdownOptions::default();
+
+		// Two different emphases right next to each other would run together
+		assert_eq!(render(b"\x1B[1ma\x1B[22;3mb\x1B[0m", &options), "**a**b\n");
+		// Punctuation between the marker and a word stops the marker from counting
+		assert_eq!(render(b"a\x1B[1m(b)\x1B[0mc", &options), "a(b)c\n");
+		assert_eq!(render(b"a \x1B[1m(b)\x1B[0m c", &options), "a **(b)** c\n");
+		assert_eq!(render(b"\x1B[1m \x1B[0m", &options), "");
+	}
+
+	#[test]
+	fn escape_test() {
+		let options = MarkdownOptions::default();
+
+		assert_eq!(
+			render(b"*not* _emphasis_ `code` [link](url) <b> a|b ~x~ #1 &amp; back\\slash", &options),
+			"\\*not\\* \\_emphasis\\_ \\`code\\` \\[link\\](url) \\<b\\> a\\|b \\~x\\~ \\#1 \\&amp; back\\\\slash\n"
+		);
+		assert_eq!(
+			render(b"- item\n+ item\n1. item\n2) item\n===", &options),
+			"\\- item\\\n\\+ item\\\n1\\. item\\\n2\\) item\\\n\\===\n"
+		);
+		assert_eq!(render(b"a - b 1. c", &options), "a - b 1. c\n");
+	}
+
+	#[test]
+	fn code_block_test() {
+		let options = MarkdownOptions::default();
+
+		assert_eq!(
+			render(b"Results:\nname    size\n\nfoo     1\n\ndone\n", &options),
+			"Results:\n\n```\nname    size\n\nfoo     1\n```\n\ndone\n"
+		);
+		assert_eq!(render("  indented\n\u{2502} box".as_bytes(), &options), "```\n  indented\n\u{2502} box\n```\n");
+		// Styles in a code block are lost without ansi blocks
+		assert_eq!(render(b"\x1B[1mlet\x1B[0m  x", &options), "```\nlet  x\n```\n");
+		assert_eq!(render(b"a  ```  b", &options), "````\na  ```  b\n````\n");
+	}
+
+	#[test]
+	fn hidden_test() {
+		let options = MarkdownOptions::default();
+
+		assert_eq!(render(b"token: \x1B[8msecret\x1B[28m", &options), "token:\n");
+		// The gap it leaves keeps the line's spacing in a code block
+		assert_eq!(render(b"a \x1B[1;8mb\x1B[0m c  d", &options), "```\na   c  d\n```\n");
+		assert_eq!(render(b"  key \x1B[8mvalue\x1B[0m\n  \x1B[8mmore", &options), "```\n  key\n```\n");
+		assert_eq!(
+			render(
+				b"\x1B[31mkey\x1B[8m value",
+				&MarkdownOptions {
+					mode: MarkdownMode::Code,
+					ansi: true,
+				}
+			),
+			"```ansi\n\x1B[0;31mkey\x1B[0;8;31m      \x1B[0m\n```\n"
+		);
+	}
+
+	#[test]
+	fn ansi_block_test() {
+		let options = MarkdownOptions {
+			ansi: true,
+			..MarkdownOptions::default()
+		};
+
+		assert_eq!(
+			render(b"\x1B[1mlet\x1B[0m  x\n\x1B[32m+\x1B[0m  y", &options),
+			"```ansi\n\x1B[0;1mlet\x1B[0m  x\n\x1B[0;32m+\x1B[0m  y\n```\n"
+		);
+		// Unstyled blocks stay plain and prose keeps using emphasis
+		assert_eq!(render(b"a  b\n\n\x1B[1mc\x1B[0m", &options), "```\na  b\n```\n\n**c**\n");
+	}
+
+	#[test]
+	fn code_mode_test() {
+		let options = MarkdownOptions {
+			mode: MarkdownMode::Code,
+			ansi: false,
+		};
+
+		assert_eq!(render(b"\x1B[1m*bold*\x1B[0m text\n\nmore\n\n", &options), "```\n*bold* text\n\nmore\n```\n");
+		assert_eq!(
+			render(
+				b"\x1B[31mred\x1B[0m",
+				&MarkdownOptions {
+					mode: MarkdownMode::Code,
+					ansi: true,
+				}
+			),
+			"```ansi\n\x1B[0;31mred\x1B[0m\n```\n"
+		);
+	}
+}
@@ -1,6 +1,10 @@
 use vte::{Parser, Perform};
 
+use crate::json::Json;
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum AnsiNode {
 	Text(String),
 	Csi {
@@ -12,6 +16,7 @@ pub enum AnsiNode {
 		intermediates: Vec<u8>,
 		byte: u8,
 	},
+	#[cfg_attr(feature = "serde", serde(rename = "control"))]
 	ControlChar(u8),
 	Osc {
 		params: Vec<Vec<u8>>,
@@ -20,6 +25,57 @@ pub enum AnsiNode {
 }
 
 impl AnsiNode {
+	/// The node as an object with its kind of text, csi, esc, control or osc as the only key, like {"text":"hi"},
+	/// the same shape the serde feature gives. Intermediates and OSC params are bytes since they don't have to be UTF-8.
+	pub(crate) fn to_json(&self) -> Json {
+		let bytes = |bytes: &[u8]| Json::Array(bytes.iter().map(|byte| Json::Number(*byte as f64)).collect());
+		let entry = |name: &str, value: Json| (String::from(name), value);
+
+		let (kind, value) = match self {
+			AnsiNode::Text(text) => ("text", Json::String(text.clone())),
+			AnsiNode::Csi {
+				params,
+				intermediates,
+				code,
+			} => (
+				"csi",
+				Json::Object(vec![
+					entry(
+						"params",
+						Json::Array(
+							params
+								.iter()
+								.map(|group| Json::Array(group.iter().map(|param| Json::Number(*param as f64)).collect()))
+								.collect(),
+						),
+					),
+					entry("intermediates", bytes(intermediates)),
+					entry("code", Json::String(code.to_string())),
+				]),
+			),
+			AnsiNode::Esc { intermediates, byte } => (
+				"esc",
+				Json::Object(vec![
+					entry("intermediates", bytes(intermediates)),
+					entry("byte", Json::Number(*byte as f64)),
+				]),
+			),
+			AnsiNode::ControlChar(byte) => ("control", Json::Number(*byte as f64)),
+			AnsiNode::Osc {
+				params,
+				bell_terminated,
+			} => (
+				"osc",
+				Json::Object(vec![
+					entry("params", Json::Array(params.iter().map(|param| bytes(param)).collect())),
+					entry("bell_terminated", Json::Bool(*bell_terminated)),
+				]),
+			),
+		};
+
+		Json::Object(vec![entry(kind, value)])
+	}
+
 	pub fn is_zero_width(&self) -> bool {
 		match self {
 			AnsiNode::Text(s) => s.is_empty(),
@@ -107,6 +163,58 @@ impl TerminalOutputParser {
 	}
 }
 
+/// Parses output that arrives in chunks, e.g. the events of a recording.
+/// Escape sequences, UTF-8 characters and CRLF split between chunks are joined up.
+pub struct TerminalOutputStream {
+	parser: Parser,
+	builder: TerminalOutputParser,
+	pending_cr: bool,
+}
+
+impl Default for TerminalOutputStream {
+	fn default() -> Self {
+		Self {
+			parser: Parser::new(),
+			builder: TerminalOutputParser {
+				nodes: Vec::new(),
+				current_text: String::new(),
+			},
+			pending_cr: false,
+		}
+	}
+}
+
+impl TerminalOutputStream {
+	/// Returns the nodes that are complete after this chunk
+	pub fn push(&mut self, input: &[u8]) -> Vec<AnsiNode> {
+		let mut chunk = Vec::with_capacity(input.len() + 1);
+		if self.pending_cr {
+			chunk.push(b'\r');
+		}
+		chunk.extend_from_slice(input);
+
+		// A trailing CR might be the start of a CRLF
+		self.pending_cr = chunk.last() == Some(&b'\r');
+		if self.pending_cr {
+			chunk.pop();
+		}
+
+		self.parser.advance(&mut self.builder, &TerminalOutputParser::normalize_crlf(&chunk));
+		self.builder.flush_text();
+		std::mem::take(&mut self.builder.nodes)
+	}
+
+	/// Returns what is left at the end of the stream
+	pub fn finish(mut self) -> Vec<AnsiNode> {
+		if self.pending_cr {
+			self.pending_cr = false;
+			self.parser.advance(&mut self.builder, b"\r");
+		}
+		self.builder.flush_text();
+		self.builder.nodes
+	}
+}
+
 impl Perform for TerminalOutputParser {
 	fn print(&mut self, c: char) {
 		self.current_text.push(c);
@@ -554,6 +662,7 @@ mod test {
 	}
 
 	#[test]
+	#[allow(clippy::needless_borrow, clippy::useless_conversion)]
 	fn very_long_input_test() {
 		let long_text = "a".repeat(10_000);
 		assert_eq!(
@@ -824,4 +933,74 @@ mod test {
 			]
 		);
 	}
+
+	#[test]
+	fn stream_test() {
+		let input = "\x1B[1;31mré\r\nd\x1B]8;;https://example.com\x07link\x1B]8;;\x07\r";
+		let mut stream = TerminalOutputStream::default();
+		let mut nodes = Vec::new();
+		// Split into single bytes so every sequence, character and CRLF is cut up
+		for byte in input.as_bytes() {
+			nodes.extend(stream.push(&[*byte]));
+		}
+		nodes.extend(stream.finish());
+
+		let text = |nodes: &[AnsiNode]| {
+			nodes
+				.iter()
+				.filter_map(|node| match node {
+					AnsiNode::Text(text) => Some(text.as_str()),
+					_ => None,
+				})
+				.collect::<String>()
+		};
+		let expected = TerminalOutputParser::parse_to_nodes(input.as_bytes());
+		assert_eq!(text(&nodes), text(&expected));
+		assert_eq!(
+			nodes.into_iter().filter(|node| !matches!(node, AnsiNode::Text(_))).collect::<Vec<_>>(),
+			expected.into_iter().filter(|node| !matches!(node, AnsiNode::Text(_))).collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn stream_chunks_test() {
+		let mut stream = TerminalOutputStream::default();
+
+		// A sequence is only returned once it is complete and text up to it is returned right away
+		assert_eq!(stream.push(b"ab\x1B[3"), vec![AnsiNode::Text(String::from("ab"))]);
+		assert_eq!(
+			stream.push(b"1mc"),
+			vec![
+				AnsiNode::Csi {
+					params: vec![vec![31]],
+					intermediates: vec![],
+					code: 'm',
+				},
+				AnsiNode::Text(String::from("c")),
+			]
+		);
+
+		// Half a character waits for the rest of it
+		assert_eq!(stream.push(&[0xC3]), vec![]);
+		assert_eq!(stream.push(&[0xA9]), vec![AnsiNode::Text(String::from("é"))]);
+
+		// A trailing CR is held back until it is clear whether it starts a CRLF
+		assert_eq!(stream.push(b"d\r"), vec![AnsiNode::Text(String::from("d"))]);
+		assert_eq!(stream.push(b"\ne\r"), vec![AnsiNode::Text(String::from("\ne"))]);
+		assert_eq!(stream.push(b"f"), vec![AnsiNode::Text(String::from("\rf"))]);
+		assert_eq!(stream.push(b"\r"), vec![]);
+		assert_eq!(stream.finish(), vec![AnsiNode::Text(String::from("\r"))]);
+
+		assert_eq!(TerminalOutputStream::default().finish(), vec![]);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn serde_shape_test() {
+		for node in TerminalOutputParser::parse_to_nodes(b"\x1B[1;38:5:208mhi\x1B(B\x07\x1B]0;t\xffitle\x07") {
+			let json = serde_json::from_str::<serde_json::Value>(&node.to_json().to_string()).unwrap();
+			assert_eq!(json, serde_json::to_value(&node).unwrap());
+			assert_eq!(serde_json::from_value::<AnsiNode>(json).unwrap(), node);
+		}
+	}
 }
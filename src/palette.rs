@@ -0,0 +1,387 @@
+use crate::styles::{Color, EightBitColor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+	pub r: u8,
+	pub g: u8,
+	pub b: u8,
+}
+
+impl Rgb {
+	pub const fn new(r: u8, g: u8, b: u8) -> Self {
+		Self { r, g, b }
+	}
+
+	pub const fn from_hex(hex: u32) -> Self {
+		Self::new((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+	}
+
+	/// Always #rrggbb since html attributes like bgcolor don't take the short #rgb form everywhere
+	pub fn to_hex(self) -> String {
+		format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+	}
+
+	/// The color halfway between this one and another, like text at half opacity over a background
+	pub fn blend(self, other: Rgb) -> Self {
+		let mix = |a: u8, b: u8| (a as u16 + b as u16).div_ceil(2) as u8;
+		Self::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+	}
+
+	/// How different two colors look, as the distance between them in the OKLab color space
+	pub fn distance(self, other: Rgb) -> f32 {
+		let (a, b) = (self.to_oklab(), other.to_oklab());
+		((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+	}
+
+	// https://bottosson.github.io/posts/oklab/
+	fn to_oklab(self) -> [f32; 3] {
+		let linear = |channel: u8| {
+			let channel = channel as f32 / 255.0;
+			if channel <= 0.04045 {
+				channel / 12.92
+			} else {
+				((channel + 0.055) / 1.055).powf(2.4)
+			}
+		};
+		let (r, g, b) = (linear(self.r), linear(self.g), linear(self.b));
+
+		let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+		let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+		let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+		[
+			0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+			1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+			0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+		]
+	}
+
+	/// Parses the X11 color specs terminals accept in OSC sequences: rgb:R/G/B with 1-4 hex digits
+	/// per channel and #RGB, #RRGGBB, #RRRGGGBBB or #RRRRGGGGBBBB
+	pub fn parse_x11(spec: &str) -> Option<Self> {
+		if let Some(channels) = spec.strip_prefix("rgb:") {
+			let mut channels = channels.split('/').map(Self::scale_channel);
+			let rgb = Self::new(channels.next()??, channels.next()??, channels.next()??);
+			return channels.next().is_none().then_some(rgb);
+		}
+
+		let hex = spec.strip_prefix('#')?;
+		if hex.is_empty() || hex.len() % 3 != 0 || hex.len() > 12 {
+			return None;
+		}
+		let digits = hex.len() / 3;
+		Some(Self::new(
+			Self::scale_channel(&hex[..digits])?,
+			Self::scale_channel(&hex[digits..digits * 2])?,
+			Self::scale_channel(&hex[digits * 2..])?,
+		))
+	}
+
+	// A channel of n hex digits is a fraction of 16^n - 1
+	fn scale_channel(hex: &str) -> Option<u8> {
+		if hex.is_empty() || hex.len() > 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+			return None;
+		}
+		let value = u32::from_str_radix(hex, 16).ok()?;
+		let max = (1u32 << (4 * hex.len())) - 1;
+		Some(((value * 255 + max / 2) / max) as u8)
+	}
+}
+
+/// How many colors the output can use
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorDepth {
+	/// Colors are kept as they are
+	#[default]
+	TrueColor,
+	/// True colors become the closest color of the 256 color palette outside the 16 theme colors
+	Palette256,
+	/// Every color becomes the closest of the 16 theme colors
+	Ansi16,
+}
+
+/// The 256 indexed colors plus the default foreground and background of a terminal.
+/// Default colors of None mean the output inherits the colors of the page it's embedded in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+	colors: [Rgb; 256],
+	pub foreground: Option<Rgb>,
+	pub background: Option<Rgb>,
+}
+
+impl Default for Palette {
+	fn default() -> Self {
+		Self::from_ansi(&Self::XTERM)
+	}
+}
+
+impl Palette {
+	pub const ANSI_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+	pub const XTERM: [Rgb; 16] = [
+		Rgb::new(0x00, 0x00, 0x00),
+		Rgb::new(0xcd, 0x00, 0x00),
+		Rgb::new(0x00, 0xcd, 0x00),
+		Rgb::new(0xcd, 0xcd, 0x00),
+		Rgb::new(0x00, 0x00, 0xee),
+		Rgb::new(0xcd, 0x00, 0xcd),
+		Rgb::new(0x00, 0xcd, 0xcd),
+		Rgb::new(0xe5, 0xe5, 0xe5),
+		Rgb::new(0x7f, 0x7f, 0x7f),
+		Rgb::new(0xff, 0x00, 0x00),
+		Rgb::new(0x00, 0xff, 0x00),
+		Rgb::new(0xff, 0xff, 0x00),
+		Rgb::new(0x5c, 0x5c, 0xff),
+		Rgb::new(0xff, 0x00, 0xff),
+		Rgb::new(0x00, 0xff, 0xff),
+		Rgb::new(0xff, 0xff, 0xff),
+	];
+
+	/// A palette with the given 16 ANSI colors and the standard 6x6x6 cube and grayscale ramp for the rest
+	pub fn from_ansi(ansi: &[Rgb; 16]) -> Self {
+		let mut colors = [Rgb::new(0, 0, 0); 256];
+		for (index, color) in colors.iter_mut().enumerate() {
+			*color = Self::default_color(index as u8, ansi);
+		}
+
+		Self {
+			colors,
+			foreground: None,
+			background: None,
+		}
+	}
+
+	fn default_color(index: u8, ansi: &[Rgb; 16]) -> Rgb {
+		match index {
+			0..=15 => ansi[index as usize],
+			16..=231 => {
+				let n = index - 16;
+				Rgb::new((n / 36) * 51, ((n % 36) / 6) * 51, (n % 6) * 51)
+			},
+			232..=255 => {
+				let gray = 8 + (index - 232) * 10;
+				Rgb::new(gray, gray, gray)
+			},
+		}
+	}
+
+	/// Names of the 16 ANSI colors like red and bright-red
+	pub fn ansi_name(index: u8) -> Option<String> {
+		match index {
+			0..=7 => Some(Self::ANSI_NAMES[index as usize].to_string()),
+			8..=15 => Some(format!("bright-{}", Self::ANSI_NAMES[index as usize - 8])),
+			_ => None,
+		}
+	}
+
+	pub fn get(&self, index: u8) -> Rgb {
+		self.colors[index as usize]
+	}
+
+	pub fn set(&mut self, index: u8, color: Rgb) {
+		self.colors[index as usize] = color;
+	}
+
+	pub fn resolve(&self, color: &Color) -> Rgb {
+		match color {
+			Color::Standard(color) => self.get(*color as u8),
+			Color::Bright(color) => self.get(8 + *color as u8),
+			Color::Palette(index) => self.get(*index),
+			Color::Rgb { r, g, b } => Rgb::new(*r, *g, *b),
+		}
+	}
+
+	/// The closest color this palette can show with the given color depth
+	pub fn reduce(&self, color: Color, depth: ColorDepth) -> Color {
+		let indices = match (depth, color) {
+			(ColorDepth::TrueColor, _)
+			| (ColorDepth::Palette256, Color::Standard(_) | Color::Bright(_) | Color::Palette(_))
+			| (ColorDepth::Ansi16, Color::Standard(_) | Color::Bright(_)) => return color,
+			(ColorDepth::Palette256, Color::Rgb { .. }) => 16..=255,
+			(ColorDepth::Ansi16, Color::Palette(index)) if index < 16 => index..=index,
+			(ColorDepth::Ansi16, _) => 0..=15,
+		};
+
+		let rgb = self.resolve(&color);
+		let index =
+			indices.min_by(|a, b| rgb.distance(self.get(*a)).total_cmp(&rgb.distance(self.get(*b)))).unwrap_or_default();
+		match (depth, index) {
+			(ColorDepth::Ansi16, 0..=7) => Color::Standard(EightBitColor::from_u8(index)),
+			(ColorDepth::Ansi16, _) => Color::Bright(EightBitColor::from_u8(index - 8)),
+			_ => Color::Palette(index),
+		}
+	}
+
+	/// Applies OSC 4, 10, 11, 104, 110 and 111 and ignores everything else including color queries.
+	/// `initial` is the palette to go back to when colors are reset.
+	pub fn apply_osc(&mut self, params: &[Vec<u8>], initial: &Palette) {
+		let Some((command, args)) = params.split_first() else {
+			return;
+		};
+		let args = args.iter().map(|arg| String::from_utf8_lossy(arg)).collect::<Vec<_>>();
+
+		match command.as_slice() {
+			// OSC 4 ; index ; spec [; index ; spec ...]
+			b"4" => {
+				for pair in args.chunks_exact(2) {
+					if let (Ok(index), Some(color)) = (pair[0].trim().parse::<u8>(), Rgb::parse_x11(pair[1].trim())) {
+						self.set(index, color);
+					}
+				}
+			},
+			// OSC 10 ; fg [; bg] where each following arg sets the next dynamic color
+			b"10" | b"11" => {
+				let first = if command.as_slice() == b"10" { 0 } else { 1 };
+				for (offset, spec) in args.iter().enumerate() {
+					let color = Rgb::parse_x11(spec.trim());
+					match (first + offset, color) {
+						(0, Some(color)) => self.foreground = Some(color),
+						(1, Some(color)) => self.background = Some(color),
+						_ => {},
+					}
+				}
+			},
+			// OSC 104 [; index ...] resets the given colors or all of them
+			b"104" => {
+				let indices = args.iter().filter_map(|arg| arg.trim().parse::<u8>().ok()).collect::<Vec<_>>();
+				if args.iter().all(|arg| arg.is_empty()) {
+					self.colors = initial.colors;
+				} else {
+					for index in indices {
+						self.set(index, initial.get(index));
+					}
+				}
+			},
+			b"110" => self.foreground = initial.foreground,
+			b"111" => self.background = initial.background,
+			_ => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parse_x11_test() {
+		assert_eq!(Rgb::parse_x11("rgb:ff/00/80"), Some(Rgb::new(255, 0, 128)));
+		assert_eq!(Rgb::parse_x11("rgb:f/0/8"), Some(Rgb::new(255, 0, 136)));
+		assert_eq!(Rgb::parse_x11("rgb:ffff/0000/8080"), Some(Rgb::new(255, 0, 128)));
+		assert_eq!(Rgb::parse_x11("#ff0080"), Some(Rgb::new(255, 0, 128)));
+		assert_eq!(Rgb::parse_x11("#f08"), Some(Rgb::new(255, 0, 136)));
+		assert_eq!(Rgb::parse_x11("#ffff00008080"), Some(Rgb::new(255, 0, 128)));
+
+		assert_eq!(Rgb::parse_x11("rgb:ff/00"), None);
+		assert_eq!(Rgb::parse_x11("rgb:ff/00/00/00"), None);
+		assert_eq!(Rgb::parse_x11("rgb:gg/00/00"), None);
+		assert_eq!(Rgb::parse_x11("#ff00"), None);
+		assert_eq!(Rgb::parse_x11("red"), None);
+		assert_eq!(Rgb::parse_x11("?"), None);
+	}
+
+	#[test]
+	fn to_hex_test() {
+		assert_eq!(Rgb::new(255, 0, 128).to_hex(), "#ff0080");
+		assert_eq!(Rgb::new(0, 0, 0).to_hex(), "#000000");
+	}
+
+	#[test]
+	fn blend_test() {
+		assert_eq!(Rgb::new(0, 0, 0).blend(Rgb::new(255, 255, 255)), Rgb::new(128, 128, 128));
+		assert_eq!(Rgb::new(200, 10, 0).blend(Rgb::new(100, 20, 1)), Rgb::new(150, 15, 1));
+	}
+
+	#[test]
+	fn distance_test() {
+		let black = Rgb::new(0, 0, 0);
+		assert_eq!(black.distance(black), 0.0);
+		assert!((black.distance(Rgb::new(255, 255, 255)) - 1.0).abs() < 0.001);
+		// Equal steps in sRGB are not equal steps to the eye
+		assert!(Rgb::new(0, 0, 255).distance(Rgb::new(0, 0, 200)) < Rgb::new(0, 255, 0).distance(Rgb::new(0, 200, 0)));
+	}
+
+	#[test]
+	fn reduce_test() {
+		let palette = Palette::default();
+		let orange = Color::Rgb { r: 250, g: 100, b: 10 };
+
+		assert_eq!(palette.reduce(orange, ColorDepth::TrueColor), orange);
+		assert_eq!(palette.reduce(orange, ColorDepth::Palette256), Color::Palette(208));
+		assert_eq!(palette.reduce(Color::Rgb { r: 30, g: 30, b: 30 }, ColorDepth::Palette256), Color::Palette(234));
+		assert_eq!(palette.reduce(Color::Palette(9), ColorDepth::Palette256), Color::Palette(9));
+
+		assert_eq!(palette.reduce(orange, ColorDepth::Ansi16), Color::Bright(EightBitColor::Red));
+		assert_eq!(palette.reduce(Color::Palette(34), ColorDepth::Ansi16), Color::Standard(EightBitColor::Green));
+		assert_eq!(palette.reduce(Color::Palette(12), ColorDepth::Ansi16), Color::Bright(EightBitColor::Blue));
+		assert_eq!(
+			palette.reduce(Color::Standard(EightBitColor::Cyan), ColorDepth::Ansi16),
+			Color::Standard(EightBitColor::Cyan)
+		);
+
+		// The 16 colors come from the theme
+		let mut palette = palette;
+		palette.set(5, Rgb::new(250, 100, 10));
+		assert_eq!(palette.reduce(orange, ColorDepth::Ansi16), Color::Standard(EightBitColor::Magenta));
+	}
+
+	#[test]
+	fn ansi_name_test() {
+		assert_eq!(Palette::ansi_name(0), Some(String::from("black")));
+		assert_eq!(Palette::ansi_name(9), Some(String::from("bright-red")));
+		assert_eq!(Palette::ansi_name(15), Some(String::from("bright-white")));
+		assert_eq!(Palette::ansi_name(16), None);
+	}
+
+	#[test]
+	fn resolve_test() {
+		let palette = Palette::default();
+		assert_eq!(palette.resolve(&Color::Standard(EightBitColor::Red)), Rgb::new(0xcd, 0, 0));
+		assert_eq!(palette.resolve(&Color::Bright(EightBitColor::Blue)), Rgb::new(0x5c, 0x5c, 0xff));
+		assert_eq!(palette.resolve(&Color::Palette(9)), Rgb::new(0xff, 0, 0));
+		assert_eq!(palette.resolve(&Color::Palette(190)), Rgb::new(0xcc, 0xff, 0));
+		assert_eq!(palette.resolve(&Color::Palette(245)), Rgb::new(0x8a, 0x8a, 0x8a));
+		assert_eq!(palette.resolve(&Color::Rgb { r: 1, g: 2, b: 3 }), Rgb::new(1, 2, 3));
+	}
+
+	#[test]
+	fn apply_osc_test() {
+		let initial = Palette::default();
+		let mut palette = initial.clone();
+
+		palette.apply_osc(
+			&[
+				b"4".to_vec(),
+				b"1".to_vec(),
+				b"rgb:12/34/56".to_vec(),
+				b"9".to_vec(),
+				b"#fff".to_vec(),
+			],
+			&initial,
+		);
+		assert_eq!(palette.resolve(&Color::Standard(EightBitColor::Red)), Rgb::new(0x12, 0x34, 0x56));
+		assert_eq!(palette.get(9), Rgb::new(255, 255, 255));
+
+		// Queries are ignored
+		palette.apply_osc(&[b"4".to_vec(), b"2".to_vec(), b"?".to_vec()], &initial);
+		assert_eq!(palette.get(2), initial.get(2));
+
+		palette.apply_osc(&[b"10".to_vec(), b"#eee".to_vec(), b"#111".to_vec()], &initial);
+		assert_eq!(palette.foreground, Some(Rgb::new(0xee, 0xee, 0xee)));
+		assert_eq!(palette.background, Some(Rgb::new(0x11, 0x11, 0x11)));
+
+		palette.apply_osc(&[b"11".to_vec(), b"rgb:00/00/00".to_vec()], &initial);
+		assert_eq!(palette.background, Some(Rgb::new(0, 0, 0)));
+
+		palette.apply_osc(&[b"104".to_vec(), b"1".to_vec()], &initial);
+		assert_eq!(palette.get(1), initial.get(1));
+		assert_eq!(palette.get(9), Rgb::new(255, 255, 255));
+
+		palette.apply_osc(&[b"104".to_vec()], &initial);
+		assert_eq!(palette.get(9), initial.get(9));
+
+		palette.apply_osc(&[b"110".to_vec()], &initial);
+		palette.apply_osc(&[b"111".to_vec()], &initial);
+		assert_eq!(palette, initial);
+	}
+}
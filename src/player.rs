@@ -0,0 +1,189 @@
+use crate::{
+	asciicast::Animation,
+	html::{HtmlOptions, HtmlRenderer, StyleMode, push_escaped},
+	styles::StyleNode,
+	themes::Theme,
+};
+
+/// A self-contained html page that plays an animation with play, pause and seek controls
+pub struct HtmlPlayer;
+
+impl HtmlPlayer {
+	// Shows the frame for the current time and keeps the seek bar and clock in sync with it
+	const SCRIPT: &str = r#"(() => {
+	const player = document.currentScript.parentElement;
+	const frames = [...player.querySelectorAll("[data-time]")];
+	const button = player.querySelector("button");
+	const seek = player.querySelector("input");
+	const clock = player.querySelector("output");
+	const duration = Number(seek.max);
+	let current = frames[0];
+	let playing = true;
+	let offset = 0;
+	let start = performance.now();
+
+	const format = (time) => `${Math.floor(time / 60)}:${String(Math.floor(time % 60)).padStart(2, "0")}`;
+
+	const show = (time) => {
+		let frame = frames[0];
+		for (const candidate of frames) {
+			if (Number(candidate.dataset.time) > time) break;
+			frame = candidate;
+		}
+		if (frame !== current) {
+			current.hidden = true;
+			frame.hidden = false;
+			current = frame;
+		}
+		seek.value = time;
+		clock.value = format(time);
+	};
+
+	const tick = (now) => {
+		if (!playing) return;
+		show((offset + (now - start) / 1000) % duration);
+		requestAnimationFrame(tick);
+	};
+
+	button.addEventListener("click", () => {
+		playing = !playing;
+		button.textContent = playing ? "Pause" : "Play";
+		if (playing) {
+			offset = Number(seek.value);
+			start = performance.now();
+			requestAnimationFrame(tick);
+		}
+	});
+
+	seek.addEventListener("input", () => {
+		offset = Number(seek.value);
+		start = performance.now();
+		show(offset);
+	});
+
+	requestAnimationFrame(tick);
+})();
+"#;
+
+	/// Renders every frame as inline styled html and hides all but the one for the current time.
+	/// The document, style mode and css variable options don't apply since the player is always a single page.
+	pub fn render(animation: &Animation, options: &HtmlOptions) -> String {
+		let frame_options = HtmlOptions {
+			document: false,
+			title_header: false,
+			style_mode: StyleMode::Inline,
+			stylesheet_href: None,
+			css_variables: None,
+			..options.clone()
+		};
+		let prefix = &options.class_prefix;
+		let last = animation.frames.last().map(|frame| &frame.screen);
+		let title = animation.title.as_deref().or_else(|| last.and_then(|screen| screen.title(options.title)));
+
+		let mut css =
+			format!("body{{margin:0;}}.{prefix}player{{display:inline-block;font-family:{};", options.font_family);
+		let palette = last.map(|screen| screen.palette());
+		css.push_str("color:");
+		StyleNode::append_rgb(&mut css, palette.and_then(|palette| palette.foreground).unwrap_or(Theme::XTERM.foreground));
+		css.push_str(";background:");
+		StyleNode::append_rgb(&mut css, palette.and_then(|palette| palette.background).unwrap_or(Theme::XTERM.background));
+		css.push_str(&format!(
+			";}}\n.{prefix}frame{{white-space:pre;padding:1em;}}\n.{prefix}controls{{display:flex;gap:.5em;align-items:center;padding:0 1em 1em;}}\n.{prefix}controls input{{flex:1;}}\n@keyframes blink{{50%{{opacity:0;}}}}\n"
+		));
+
+		let mut html = String::from("<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+		if let Some(title) = title {
+			html.push_str("<title>");
+			push_escaped(&mut html, title);
+			html.push_str("</title>\n");
+		}
+		html.push_str("<style>\n");
+		push_escaped_css(&mut html, &css);
+		html.push_str("</style>\n</head>\n<body>\n");
+
+		if options.title_header
+			&& let Some(title) = title
+		{
+			html.push_str("<h1>");
+			push_escaped(&mut html, title);
+			html.push_str("</h1>\n");
+		}
+
+		html.push_str("<div class=\"");
+		push_escaped(&mut html, prefix);
+		html.push_str("player\">\n");
+
+		for (index, frame) in animation.frames.iter().enumerate() {
+			html.push_str("<div class=\"");
+			push_escaped(&mut html, prefix);
+			html.push_str(&format!("frame\" data-time=\"{}\"", frame.time));
+			if index > 0 {
+				html.push_str(" hidden");
+			}
+			html.push('>');
+			html.push_str(&HtmlRenderer::render_viewport(&frame.screen, &frame_options));
+			html.push_str("</div>\n");
+		}
+
+		html.push_str("<div class=\"");
+		push_escaped(&mut html, prefix);
+		html.push_str(&format!(
+			"controls\"><button type=\"button\">Pause</button><input type=\"range\" min=\"0\" max=\"{}\" step=\"0.01\" value=\"0\" aria-label=\"Seek\"><output>0:00</output></div>\n",
+			animation.duration
+		));
+		html.push_str("<script>\n");
+		html.push_str(Self::SCRIPT);
+		html.push_str("</script>\n</div>\n</body>\n</html>");
+
+		html
+	}
+}
+
+// The font stack goes into the CSS as is, so only the end of the style element needs guarding
+fn push_escaped_css(html: &mut String, css: &str) {
+	html.push_str(&css.replace("</", "<\\/"));
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::asciicast::Asciicast;
+	use crate::palette::Palette;
+
+	#[test]
+	fn render_test() {
+		let cast = Asciicast {
+			width: 3,
+			height: 1,
+			title: Some(String::from("<demo>")),
+			palette: None,
+			events: vec![(1.5, String::from("\x1B[1ma"))],
+		};
+		let html = HtmlPlayer::render(&cast.animation(Palette::default()), &HtmlOptions::default());
+
+		assert!(html.starts_with("<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>&lt;demo&gt;</title>\n<style>\nbody{margin:0;}.sv-player{display:inline-block;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,'Liberation Mono',monospace;color:#000;background:#fff;}\n"));
+		assert!(html.contains("<div class=\"sv-player\">\n<div class=\"sv-frame\" data-time=\"0\"></div>\n<div class=\"sv-frame\" data-time=\"1.5\" hidden><span style=\"font-weight:bold;\">a</span></div>\n"));
+		assert!(
+			html.contains("<input type=\"range\" min=\"0\" max=\"3.5\" step=\"0.01\" value=\"0\" aria-label=\"Seek\">")
+		);
+		assert!(html.ends_with("})();\n</script>\n</div>\n</body>\n</html>"));
+	}
+
+	#[test]
+	fn font_family_cannot_close_style_test() {
+		let cast = Asciicast {
+			width: 1,
+			height: 1,
+			title: None,
+			palette: None,
+			events: Vec::new(),
+		};
+		let options = HtmlOptions {
+			font_family: String::from("a</style><script>"),
+			..HtmlOptions::default()
+		};
+		let html = HtmlPlayer::render(&cast.animation(Palette::default()), &options);
+
+		assert!(!html.contains("</style><script>"));
+	}
+}
@@ -0,0 +1,226 @@
+use crate::{
+	palette::Rgb,
+	screen::Screen,
+	styles::{StyleNode, UnderlineStyle},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtfOptions {
+	/// A single font name, word processors fall back to any monospace font when it's missing
+	pub font_family: String,
+	/// In points
+	pub font_size: f32,
+}
+
+impl Default for RtfOptions {
+	fn default() -> Self {
+		Self {
+			font_family: String::from(Self::DEFAULT_FONT_FAMILY),
+			font_size: 10.0,
+		}
+	}
+}
+
+impl RtfOptions {
+	pub const DEFAULT_FONT_FAMILY: &str = "Courier New";
+}
+
+// What of a style RTF can show, with colors already resolved against the palette
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RtfStyle {
+	foreground: Option<Rgb>,
+	background: Option<Rgb>,
+	bold: bool,
+	italic: bool,
+	underline: Option<UnderlineStyle>,
+	strikethrough: bool,
+	hidden: bool,
+}
+
+/// A rich text document for pasting into word processors and email clients
+pub struct RtfRenderer {
+	rtf: String,
+	// The color table without the automatic color at index 0
+	colors: Vec<Rgb>,
+}
+
+impl RtfRenderer {
+	pub fn render_screen(screen: &Screen, options: &RtfOptions) -> String {
+		let mut lines = screen.lines().collect::<Vec<_>>();
+		while lines.last().is_some_and(|line| line.is_empty()) {
+			lines.pop();
+		}
+
+		let mut renderer = Self {
+			rtf: String::new(),
+			colors: Vec::new(),
+		};
+		let palette = screen.palette();
+
+		for (row, line) in lines.iter().enumerate() {
+			if row > 0 {
+				renderer.rtf.push_str("\\par\n");
+			}
+
+			let mut runs: Vec<(RtfStyle, String)> = Vec::new();
+			for (ch, style) in line.iter().map(|cell| cell.map_or((' ', StyleNode::default()), |cell| (cell.ch, cell.style)))
+			{
+				// Unknown default colors are left to the document, which is black on white in most editors
+				let (mut foreground, background) = style.resolved_colors(palette);
+				foreground = foreground.or(palette.foreground);
+				let background = background.or(palette.background);
+				if style.dim()
+					&& let Some(color) = foreground
+				{
+					foreground = Some(color.blend(background.unwrap_or(Rgb::new(255, 255, 255))));
+				}
+
+				let style = RtfStyle {
+					foreground,
+					background,
+					bold: style.bold(),
+					italic: style.italic(),
+					underline: style.underline(),
+					strikethrough: style.strikethrough(),
+					hidden: style.hidden(),
+				};
+				// Hidden text keeps its space so the columns around it stay in place
+				let ch = if style.hidden { ' ' } else { ch };
+				match runs.last_mut() {
+					Some((last, text)) if *last == style => text.push(ch),
+					_ => runs.push((style, ch.to_string())),
+				}
+			}
+
+			for (style, text) in runs {
+				renderer.push_run(&style, &text);
+			}
+		}
+
+		let mut rtf = String::from("{\\rtf1\\ansi\\deff0\n{\\fonttbl{\\f0\\fmodern ");
+		push_escaped(&mut rtf, &options.font_family);
+		rtf.push_str(";}}\n{\\colortbl;");
+		for color in &renderer.colors {
+			rtf.push_str(&format!("\\red{}\\green{}\\blue{};", color.r, color.g, color.b));
+		}
+		// Font sizes are in half points
+		rtf.push_str(&format!("}}\n\\f0\\fs{}\n", (options.font_size * 2.0).round() as u32));
+		rtf.push_str(&renderer.rtf);
+		rtf.push_str("\n}");
+		rtf
+	}
+
+	fn push_run(&mut self, style: &RtfStyle, text: &str) {
+		let mut controls = String::new();
+		if let Some(color) = style.foreground {
+			controls.push_str(&format!("\\cf{}", self.color_index(color)));
+		}
+		// Word ignores \cb so the background is also set as character shading
+		if let Some(color) = style.background {
+			let index = self.color_index(color);
+			controls.push_str(&format!("\\cb{index}\\chshdng0\\chcbpat{index}"));
+		}
+		if style.bold {
+			controls.push_str("\\b");
+		}
+		if style.italic {
+			controls.push_str("\\i");
+		}
+		controls.push_str(match style.underline {
+			Some(UnderlineStyle::Single) => "\\ul",
+			Some(UnderlineStyle::Double) => "\\uldb",
+			Some(UnderlineStyle::Curly) => "\\ulwave",
+			Some(UnderlineStyle::Dotted) => "\\uld",
+			Some(UnderlineStyle::Dashed) => "\\uldash",
+			None => "",
+		});
+		if style.strikethrough {
+			controls.push_str("\\strike");
+		}
+
+		if controls.is_empty() {
+			push_escaped(&mut self.rtf, text);
+		} else {
+			self.rtf.push('{');
+			self.rtf.push_str(&controls);
+			self.rtf.push(' ');
+			push_escaped(&mut self.rtf, text);
+			self.rtf.push('}');
+		}
+	}
+
+	fn color_index(&mut self, color: Rgb) -> usize {
+		match self.colors.iter().position(|known| *known == color) {
+			Some(index) => index + 1,
+			None => {
+				self.colors.push(color);
+				self.colors.len()
+			},
+		}
+	}
+}
+
+// RTF is 7-bit so everything else goes in as \uN? with N the signed 16 bit UTF-16 code unit and ? the fallback
+fn push_escaped(rtf: &mut String, text: &str) {
+	for ch in text.chars() {
+		match ch {
+			'\\' | '{' | '}' => {
+				rtf.push('\\');
+				rtf.push(ch);
+			},
+			'\t' => rtf.push_str("\\tab "),
+			' '..='~' => rtf.push(ch),
+			_ => {
+				let mut units = [0; 2];
+				for unit in ch.encode_utf16(&mut units) {
+					rtf.push_str(&format!("\\u{}?", *unit as i16));
+				}
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{nodes::TerminalOutputParser, themes::Theme};
+
+	fn render(input: &[u8], screen: Screen) -> String {
+		let mut screen = screen;
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(input));
+		RtfRenderer::render_screen(&screen, &RtfOptions::default())
+	}
+
+	#[test]
+	fn plain_text_test() {
+		assert_eq!(
+			render(b"hello {world}\\\nline two\n", Screen::default()),
+			"{\\rtf1\\ansi\\deff0\n{\\fonttbl{\\f0\\fmodern Courier New;}}\n{\\colortbl;}\n\\f0\\fs20\nhello \\{world\\}\\\\\\par\nline two\n}"
+		);
+		assert_eq!(
+			render("caf\u{e9} \u{1F980}".as_bytes(), Screen::default()),
+			"{\\rtf1\\ansi\\deff0\n{\\fonttbl{\\f0\\fmodern Courier New;}}\n{\\colortbl;}\n\\f0\\fs20\ncaf\\u233? \\u-10178?\\u-8832?\n}"
+		);
+	}
+
+	#[test]
+	fn styled_text_test() {
+		assert_eq!(
+			render(
+				b"\x1B[1;31mError:\x1B[0m \x1B[3;4:3mwavy\x1B[0m \x1B[9;38;5;208mold\x1B[0m \x1B[31;42mboth\x1B[0m",
+				Screen::default()
+			),
+			"{\\rtf1\\ansi\\deff0\n{\\fonttbl{\\f0\\fmodern Courier New;}}\n{\\colortbl;\\red255\\green0\\blue0;\\red255\\green102\\blue0;\\red205\\green0\\blue0;\\red0\\green205\\blue0;}\n\\f0\\fs20\n{\\cf1\\b Error:} {\\i\\ulwave wavy} {\\cf2\\strike old} {\\cf3\\cb4\\chshdng0\\chcbpat4 both}\n}"
+		);
+	}
+
+	#[test]
+	fn default_colors_test() {
+		// Known default colors go on every run while reverse and dim use them as well
+		let screen = Screen::default().with_palette(Theme::XTERM.palette());
+		assert_eq!(
+			render(b"a\x1B[7mb\x1B[0m\x1B[2mc\x1B[0m\x1B[8md", screen),
+			"{\\rtf1\\ansi\\deff0\n{\\fonttbl{\\f0\\fmodern Courier New;}}\n{\\colortbl;\\red0\\green0\\blue0;\\red255\\green255\\blue255;\\red128\\green128\\blue128;}\n\\f0\\fs20\n{\\cf1\\cb2\\chshdng0\\chcbpat2 a}{\\cf2\\cb1\\chshdng0\\chcbpat1 b}{\\cf3\\cb2\\chshdng0\\chcbpat2 c}{\\cf1\\cb2\\chshdng0\\chcbpat2  }\n}"
+		);
+	}
+}
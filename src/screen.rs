@@ -0,0 +1,946 @@
+use crate::{
+	nodes::AnsiNode,
+	palette::{ColorDepth, Palette},
+	styles::StyleNode,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+	pub ch: char,
+	pub style: StyleNode,
+	/// Index into [`Screen::links`]
+	pub link: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperlink {
+	pub id: Option<String>,
+	pub url: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CarriageReturn {
+	/// Return to column zero so following text overwrites the line and only its final state remains
+	#[default]
+	Overwrite,
+	/// Same as overwrite but every state of the line before a carriage return is kept as its own line
+	KeepFrames,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TitleSelection {
+	/// The first title set in the stream
+	First,
+	/// The title the terminal showed at the end of the stream
+	#[default]
+	Last,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Cursor {
+	row: usize,
+	col: usize,
+}
+
+/// A virtual terminal that replays text, SGR and cursor movement onto a grid of cells.
+/// Lines are never dropped so everything that scrolled out of view is kept as scrollback, unless it's turned off.
+/// Without a width lines never wrap and without a height the viewport spans the whole buffer.
+#[derive(Debug, Clone, Default)]
+pub struct Screen {
+	lines: Vec<Vec<Option<Cell>>>,
+	width: Option<usize>,
+	height: Option<usize>,
+	top: usize,
+	cursor: Cursor,
+	saved_cursor: Option<Cursor>,
+	style: StyleNode,
+	carriage_return: CarriageReturn,
+	overstrike: bool,
+	links: Vec<Hyperlink>,
+	link: Option<usize>,
+	first_title: Option<String>,
+	title: Option<String>,
+	title_stack: Vec<Option<String>>,
+	palette: Palette,
+	initial_palette: Palette,
+	drop_scrollback: bool,
+}
+
+impl Screen {
+	const TAB_WIDTH: usize = 8;
+	// Without a width or height a single cursor sequence could ask for millions of cells so the cursor stops at this
+	// column and at this many rows past the last line, like it would at the edge of a very large terminal
+	pub const MAX_COLUMNS: usize = 1000;
+	pub const MAX_ROWS: usize = 1000;
+	// Same depth as xterm, pushing onto a full stack drops the oldest title
+	const MAX_TITLE_STACK: usize = 10;
+
+	pub fn new(width: Option<usize>, height: Option<usize>) -> Self {
+		Self {
+			width: width.filter(|width| *width > 0),
+			height: height.filter(|height| *height > 0),
+			..Self::default()
+		}
+	}
+
+	pub fn with_carriage_return(mut self, carriage_return: CarriageReturn) -> Self {
+		self.carriage_return = carriage_return;
+		self
+	}
+
+	/// The palette the terminal starts with and returns to when colors are reset
+	pub fn with_palette(mut self, palette: Palette) -> Self {
+		self.palette = palette.clone();
+		self.initial_palette = palette;
+		self
+	}
+
+	/// Forget lines once they scroll out of view, for screens that are only ever looked at through the viewport
+	pub fn without_scrollback(mut self) -> Self {
+		self.drop_scrollback = true;
+		self
+	}
+
+	/// A copy of what is in view with only the links it uses, which stays small however much output came before
+	pub fn snapshot(&self) -> Self {
+		let mut links: Vec<Hyperlink> = Vec::new();
+		let mut lines = Vec::new();
+		for line in self.viewport() {
+			let line = line.iter().map(|cell| {
+				cell.map(|cell| Cell {
+					link: cell.link.and_then(|link| self.links.get(link)).map(|link| {
+						links.iter().position(|known| known == link).unwrap_or_else(|| {
+							links.push(link.clone());
+							links.len() - 1
+						})
+					}),
+					..cell
+				})
+			});
+			lines.push(line.collect());
+		}
+
+		Self {
+			lines,
+			width: self.width,
+			height: self.height,
+			cursor: Cursor {
+				row: self.cursor.row.saturating_sub(self.top),
+				col: self.cursor.col,
+			},
+			links,
+			first_title: self.first_title.clone(),
+			title: self.title.clone(),
+			palette: self.palette.clone(),
+			initial_palette: self.initial_palette.clone(),
+			drop_scrollback: self.drop_scrollback,
+			..Self::default()
+		}
+	}
+
+	pub fn from_nodes(nodes: &[AnsiNode]) -> Self {
+		let mut screen = Self::default();
+		screen.apply_all(nodes);
+		screen
+	}
+
+	pub fn apply_all(&mut self, nodes: &[AnsiNode]) {
+		for node in nodes {
+			self.apply(node);
+		}
+	}
+
+	pub fn apply(&mut self, node: &AnsiNode) {
+		match node {
+			AnsiNode::Text(text) => {
+				for c in text.chars() {
+					self.put(c);
+				}
+			},
+			AnsiNode::Csi {
+				params,
+				intermediates,
+				code,
+			} if intermediates.is_empty() => self.csi(params, *code),
+			AnsiNode::Esc { intermediates, byte } if intermediates.is_empty() => self.esc(*byte),
+			AnsiNode::ControlChar(0x08) => self.backspace(),
+			AnsiNode::Osc { params, .. } => self.osc(params),
+			_ => {},
+		}
+	}
+
+	/// Turns the colors of every cell into the closest ones the final palette can show with fewer colors
+	pub fn reduce_colors(&mut self, depth: ColorDepth) {
+		for cell in self.lines.iter_mut().flatten().flatten() {
+			cell.style.reduce_colors(&self.palette, depth);
+		}
+	}
+
+	pub fn links(&self) -> &[Hyperlink] {
+		&self.links
+	}
+
+	/// The palette at the end of the stream as terminals recolor everything on screen when the palette changes
+	pub fn palette(&self) -> &Palette {
+		&self.palette
+	}
+
+	pub fn title(&self, selection: TitleSelection) -> Option<&str> {
+		match selection {
+			TitleSelection::First => self.first_title.as_deref(),
+			TitleSelection::Last => self.title.as_deref(),
+		}
+	}
+
+	/// All lines down to the last written line or the cursor, whichever is further down, with trailing blank cells removed
+	pub fn lines(&self) -> impl Iterator<Item = &[Option<Cell>]> {
+		let last_written = self.lines.iter().rposition(|line| line.iter().any(Option::is_some)).map_or(0, |row| row + 1);
+		let count = last_written.max(self.cursor.row + 1);
+
+		(0..count).map(|row| {
+			let line = self.lines.get(row).map_or(&[][..], Vec::as_slice);
+			let len = line.iter().rposition(Option::is_some).map_or(0, |col| col + 1);
+			&line[..len]
+		})
+	}
+
+	/// The lines in view, always `height` lines on a screen with a height and the same as `lines` without one
+	pub fn viewport(&self) -> Vec<&[Option<Cell>]> {
+		let Some(height) = self.height else {
+			return self.lines().collect();
+		};
+
+		(self.top..self.top + height)
+			.map(|row| {
+				let line = self.lines.get(row).map_or(&[][..], Vec::as_slice);
+				let len = line.iter().rposition(Option::is_some).map_or(0, |col| col + 1);
+				&line[..len]
+			})
+			.collect()
+	}
+
+	fn put(&mut self, c: char) {
+		match c {
+			'\n' => self.line_feed(true),
+			'\r' => self.carriage_return(),
+			'\t' => self.tab(),
+			_ => self.print(c),
+		}
+	}
+
+	fn print(&mut self, ch: char) {
+		if let Some(width) = self.width
+			&& self.cursor.col >= width
+		{
+			self.line_feed(true);
+		}
+
+		let Cursor { row, col } = self.cursor;
+		let overstrike = std::mem::take(&mut self.overstrike);
+		let mut style = self.style;
+		let link = self.link;
+		let line = self.line_mut(row);
+		if line.len() <= col {
+			line.resize(col + 1, None);
+		}
+
+		let mut ch = ch;
+		if overstrike && let Some(previous) = line[col] {
+			// nroff style overstrike as used by man pages: c\bc is bold and _\bc or c\b_ is underlined
+			if previous.ch == ch {
+				style = previous.style;
+				style.apply_sgr(&[vec![1]]);
+			} else if previous.ch == '_' {
+				style = previous.style;
+				style.apply_sgr(&[vec![4]]);
+			} else if ch == '_' {
+				ch = previous.ch;
+				style = previous.style;
+				style.apply_sgr(&[vec![4]]);
+			}
+		}
+
+		line[col] = Some(Cell { ch, style, link });
+		self.cursor.col += 1;
+	}
+
+	fn backspace(&mut self) {
+		if self.cursor.col > 0 {
+			self.cursor.col -= 1;
+			self.overstrike = true;
+		}
+	}
+
+	fn carriage_return(&mut self) {
+		if self.carriage_return == CarriageReturn::KeepFrames {
+			let row = self.cursor.row;
+			let frame = self.line_mut(row).clone();
+			if frame.iter().any(Option::is_some) {
+				self.insert_lines(row, 1);
+				self.lines[row] = frame;
+				self.line_feed(false);
+			}
+		}
+
+		self.cursor.col = 0;
+	}
+
+	fn tab(&mut self) {
+		let next_stop = (self.cursor.col / Self::TAB_WIDTH + 1) * Self::TAB_WIDTH;
+		self.set_col(next_stop);
+	}
+
+	fn csi(&mut self, params: &[Vec<u16>], code: char) {
+		self.overstrike = false;
+		let n = Self::param(params, 0, 1);
+
+		match code {
+			'm' => self.style.apply_sgr(params),
+
+			// Cursor movement
+			'A' => self.move_up(n),
+			'B' => self.move_down(n),
+			'C' => self.set_col(self.cursor.col + n),
+			'D' => self.set_col(self.cursor.col.saturating_sub(n)),
+			'E' => {
+				self.move_down(n);
+				self.cursor.col = 0;
+			},
+			'F' => {
+				self.move_up(n);
+				self.cursor.col = 0;
+			},
+			'G' | '`' => self.set_col(n - 1),
+			'd' => self.set_row(n - 1),
+			'H' | 'f' => {
+				self.set_row(n - 1);
+				self.set_col(Self::param(params, 1, 1) - 1);
+			},
+			's' => self.saved_cursor = Some(self.cursor),
+			'u' => self.restore_cursor(),
+
+			't' => {
+				// Window title stack where the second param picks both (0), the icon name (1) or the window title (2)
+				let window_title = !matches!(params.get(1).map(Vec::as_slice), Some([1]));
+				match Self::param(params, 0, 0) {
+					22 if window_title => {
+						if self.title_stack.len() == Self::MAX_TITLE_STACK {
+							self.title_stack.remove(0);
+						}
+						self.title_stack.push(self.title.clone());
+					},
+					23 if window_title => {
+						if let Some(title) = self.title_stack.pop() {
+							self.title = title;
+						}
+					},
+					_ => {},
+				}
+			},
+
+			// Erasing
+			'J' => self.erase_in_display(Self::param(params, 0, 0)),
+			'K' => self.erase_in_line(Self::param(params, 0, 0)),
+			'X' => {
+				let col = self.cursor.col;
+				let line = self.line_mut(self.cursor.row);
+				let end = (col + n).min(line.len());
+				if col < end {
+					line[col..end].fill(None);
+				}
+			},
+
+			// Inserting and deleting
+			'@' => {
+				let col = self.cursor.col;
+				let width = self.width;
+				let line = self.line_mut(self.cursor.row);
+				if col < line.len() {
+					// Cells pushed past the right edge are lost
+					let edge = width.unwrap_or(Self::MAX_COLUMNS.max(line.len()));
+					line.splice(col..col, std::iter::repeat_n(None, n.min(edge)));
+					line.truncate(edge);
+				}
+			},
+			'P' => {
+				let col = self.cursor.col;
+				let line = self.line_mut(self.cursor.row);
+				if col < line.len() {
+					let end = (col + n).min(line.len());
+					line.drain(col..end);
+				}
+			},
+			'L' => self.insert_lines(self.cursor.row, n),
+			'M' => self.delete_lines(self.cursor.row, n),
+			// Scrolling only moves a viewport, a screen without a height would lose the start of its output instead
+			'S' if self.height.is_some() => self.delete_lines(self.top, n),
+			'T' if self.height.is_some() => self.insert_lines(self.top, n),
+
+			_ => {},
+		}
+	}
+
+	fn esc(&mut self, byte: u8) {
+		self.overstrike = false;
+		match byte {
+			b'7' => self.saved_cursor = Some(self.cursor),
+			b'8' => self.restore_cursor(),
+			b'D' => self.line_feed(false),
+			b'E' => self.line_feed(true),
+			b'M' => {
+				if self.cursor.row == self.top {
+					self.insert_lines(self.top, 1);
+				} else {
+					self.cursor.row -= 1;
+				}
+			},
+			_ => {},
+		}
+	}
+
+	fn osc(&mut self, params: &[Vec<u8>]) {
+		match params {
+			[command, ..] if matches!(command.as_slice(), b"4" | b"10" | b"11" | b"104" | b"110" | b"111") => {
+				self.palette.apply_osc(params, &self.initial_palette);
+			},
+			// OSC 0 sets icon name and window title, OSC 2 only the window title
+			[command, title @ ..] if command == b"0" || command == b"2" => {
+				let title = title.iter().map(|part| String::from_utf8_lossy(part)).collect::<Vec<_>>().join(";");
+				if self.first_title.is_none() && !title.is_empty() {
+					self.first_title = Some(title.clone());
+				}
+				self.title = Some(title).filter(|title| !title.is_empty());
+			},
+			// OSC 8 ; params ; url where params are colon separated key=value pairs
+			[command, link_params, url @ ..] if command == b"8" => {
+				// Urls may contain semicolons which the parser splits on
+				let url = url.iter().map(|part| String::from_utf8_lossy(part)).collect::<Vec<_>>().join(";");
+				if url.is_empty() {
+					self.link = None;
+					return;
+				}
+
+				let id = String::from_utf8_lossy(link_params)
+					.split(':')
+					.find_map(|pair| pair.strip_prefix("id=").map(String::from))
+					.filter(|id| !id.is_empty());
+
+				let link = Hyperlink { id, url };
+				// Links sharing an id are one link even when split across lines or interrupted by other output
+				let existing = link.id.as_ref().and_then(|_| self.links.iter().position(|known| *known == link));
+				self.link = Some(existing.unwrap_or_else(|| {
+					self.links.push(link);
+					self.links.len() - 1
+				}));
+			},
+			_ => {},
+		}
+	}
+
+	// Zero and missing params both mean "use the default" for every sequence we handle
+	fn param(params: &[Vec<u16>], index: usize, default: usize) -> usize {
+		match params.get(index).and_then(|group| group.first()) {
+			Some(0) | None => default,
+			Some(value) => *value as usize,
+		}
+	}
+
+	fn line_mut(&mut self, row: usize) -> &mut Vec<Option<Cell>> {
+		if self.lines.len() <= row {
+			self.lines.resize_with(row + 1, Vec::new);
+		}
+		&mut self.lines[row]
+	}
+
+	fn bottom(&self) -> Option<usize> {
+		self.height.map(|height| self.top + height - 1)
+	}
+
+	fn line_feed(&mut self, carriage_return: bool) {
+		self.overstrike = false;
+		self.cursor.row += 1;
+		if carriage_return {
+			self.cursor.col = 0;
+		}
+
+		if let Some(height) = self.height
+			&& self.cursor.row >= self.top + height
+		{
+			self.top = self.cursor.row + 1 - height;
+			if self.drop_scrollback {
+				self.erase_in_display(3);
+			}
+		}
+	}
+
+	fn move_up(&mut self, n: usize) {
+		self.cursor.row = self.cursor.row.saturating_sub(n).max(self.top);
+	}
+
+	// The lowest row the cursor can be moved to
+	fn last_row(&self) -> usize {
+		self.bottom().unwrap_or(self.lines.len().max(self.cursor.row) + Self::MAX_ROWS - 1)
+	}
+
+	fn move_down(&mut self, n: usize) {
+		self.cursor.row = (self.cursor.row + n).min(self.last_row());
+	}
+
+	fn set_row(&mut self, row: usize) {
+		self.cursor.row = (self.top + row).min(self.last_row());
+	}
+
+	// Text can go past MAX_COLUMNS on a screen without a width, cursor movement only stops going further right
+	fn set_col(&mut self, col: usize) {
+		self.cursor.col = match self.width {
+			Some(width) => col.min(width - 1),
+			None => col.min((Self::MAX_COLUMNS - 1).max(self.cursor.col)),
+		};
+	}
+
+	fn restore_cursor(&mut self) {
+		if let Some(cursor) = self.saved_cursor {
+			self.cursor.col = cursor.col;
+			// The screen may have scrolled since the cursor was saved so it can't end up above the viewport
+			self.cursor.row = cursor.row.max(self.top);
+		}
+	}
+
+	fn erase_in_display(&mut self, mode: usize) {
+		match mode {
+			// Cursor to end of screen
+			0 => {
+				self.erase_in_line(0);
+				self.lines.truncate(self.cursor.row + 1);
+			},
+			// Start of screen to cursor
+			1 => {
+				for row in self.top..self.cursor.row {
+					self.line_mut(row).clear();
+				}
+				self.erase_in_line(1);
+			},
+			// Whole screen
+			2 => {
+				let top = self.top;
+				for line in self.lines.iter_mut().skip(top) {
+					line.clear();
+				}
+			},
+			// Scrollback
+			3 => {
+				let top = self.top.min(self.lines.len());
+				self.lines.drain(..top);
+				self.cursor.row = self.cursor.row.saturating_sub(top);
+				if let Some(saved) = self.saved_cursor.as_mut() {
+					saved.row = saved.row.saturating_sub(top);
+				}
+				self.top = 0;
+			},
+			_ => {},
+		}
+	}
+
+	fn erase_in_line(&mut self, mode: usize) {
+		let col = self.cursor.col;
+		let line = self.line_mut(self.cursor.row);
+
+		match mode {
+			// Cursor to end of line
+			0 => line.truncate(col),
+			// Start of line to cursor
+			1 => {
+				let end = (col + 1).min(line.len());
+				line[..end].fill(None);
+			},
+			// Whole line
+			2 => line.clear(),
+			_ => {},
+		}
+	}
+
+	fn insert_lines(&mut self, row: usize, n: usize) {
+		self.line_mut(row);
+		let n = n.min(self.height.unwrap_or(Self::MAX_ROWS));
+		self.lines.splice(row..row, std::iter::repeat_n(Vec::new(), n));
+
+		if let Some(bottom) = self.bottom() {
+			self.lines.truncate(bottom + 1);
+		}
+	}
+
+	fn delete_lines(&mut self, row: usize, n: usize) {
+		if row < self.lines.len() {
+			let end = (row + n).min(self.lines.len());
+			self.lines.drain(row..end);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn screen_text(screen: &Screen) -> String {
+		screen
+			.lines()
+			.map(|line| line.iter().map(|cell| cell.map_or(' ', |cell| cell.ch)).collect::<String>())
+			.collect::<Vec<String>>()
+			.join("\n")
+	}
+
+	fn render(input: &[u8]) -> String {
+		screen_text(&Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input)))
+	}
+
+	fn render_sized(input: &[u8], width: usize, height: usize) -> String {
+		let mut screen = Screen::new(Some(width), Some(height));
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(input));
+		screen_text(&screen)
+	}
+
+	#[test]
+	fn plain_text_test() {
+		assert_eq!(render(b"hello\nworld"), String::from("hello\nworld"));
+		assert_eq!(render(b"hello\n"), String::from("hello\n"));
+		assert_eq!(render(b""), String::new());
+		assert_eq!(render(b"a\tb"), String::from("a       b"));
+	}
+
+	#[test]
+	fn cursor_movement_test() {
+		assert_eq!(render(b"abc\x1B[2DX"), String::from("aXc"));
+		assert_eq!(render(b"a\x1B[3Cb"), String::from("a   b"));
+		assert_eq!(render(b"line1\nline2\x1B[1AX"), String::from("line1X\nline2"));
+		assert_eq!(render(b"one\ntwo\nthree\x1B[2;2HX"), String::from("one\ntXo\nthree"));
+		assert_eq!(render(b"abc\x1B[GX"), String::from("Xbc"));
+		assert_eq!(render(b"a\nb\nc\x1B[2FX"), String::from("X\nb\nc"));
+		assert_eq!(render(b"a\x1B[2EX"), String::from("a\n\nX"));
+		assert_eq!(render(b"a\nb\nc\x1B[1dX"), String::from("aX\nb\nc"));
+	}
+
+	#[test]
+	fn save_restore_cursor_test() {
+		assert_eq!(render(b"ab\x1B[s\ncd\x1B[uX"), String::from("abX\ncd"));
+		assert_eq!(render(b"ab\x1B7\ncd\x1B8X"), String::from("abX\ncd"));
+		assert_eq!(render(b"ab\x1B[uX"), String::from("abX"));
+	}
+
+	#[test]
+	fn erase_in_line_test() {
+		assert_eq!(render(b"hello\x1B[3D\x1B[K"), String::from("he"));
+		assert_eq!(render(b"hello\x1B[3D\x1B[1K"), String::from("   lo"));
+		assert_eq!(render(b"hello\x1B[2KX"), String::from("     X"));
+		assert_eq!(render(b"hello\x1B[2G\x1B[3X"), String::from("h   o"));
+	}
+
+	#[test]
+	fn erase_in_display_test() {
+		assert_eq!(render(b"old\nstuff\x1B[2J\x1B[Hnew"), String::from("new"));
+		assert_eq!(render(b"one\ntwo\nthree\x1B[2;2H\x1B[J"), String::from("one\nt"));
+		assert_eq!(render(b"one\ntwo\nthree\x1B[2;2H\x1B[1J"), String::from("\n  o\nthree"));
+	}
+
+	#[test]
+	fn insert_delete_chars_test() {
+		assert_eq!(render(b"abcdef\x1B[4G\x1B[2P"), String::from("abcf"));
+		assert_eq!(render(b"abcdef\x1B[4G\x1B[2@XY"), String::from("abcXYdef"));
+	}
+
+	#[test]
+	fn insert_delete_lines_test() {
+		assert_eq!(render(b"one\ntwo\nthree\x1B[2;1H\x1B[L"), String::from("one\n\ntwo\nthree"));
+		assert_eq!(render(b"one\ntwo\nthree\x1B[1;1H\x1B[2M"), String::from("three"));
+		assert_eq!(render_sized(b"1\n2\n3\x1B[1;1H\x1B[L", 10, 3), String::from("\n1\n2"));
+	}
+
+	#[test]
+	fn scrolling_test() {
+		assert_eq!(render_sized(b"1\n2\n3\n4\x1B[1;1HX", 10, 2), String::from("1\n2\nX\n4"));
+		assert_eq!(render_sized(b"1\n2\n3\x1B[S", 10, 2), String::from("1\n3\n"));
+		assert_eq!(render_sized(b"1\n2\x1B[1;1H\x1BMX", 10, 2), String::from("X\n1"));
+		assert_eq!(render_sized(b"1\n2\n3\n4\x1B[3J", 10, 2), String::from("3\n4"));
+		// A cursor saved before scrolling comes back inside the viewport
+		assert_eq!(render_sized(b"\x1B7a\nb\nc\nd\x1B8X\x1B[3J", 10, 2), String::from("X\nd"));
+	}
+
+	#[test]
+	fn wrapping_test() {
+		assert_eq!(render_sized(b"abcdef", 4, 10), String::from("abcd\nef"));
+		assert_eq!(render_sized(b"abcd\x1B[10CX", 4, 10), String::from("abcX"));
+		assert_eq!(render(&[b'a'; 200]), String::from_utf8(vec![b'a'; 200]).unwrap());
+	}
+
+	#[test]
+	fn size_limit_test() {
+		let repeat = |sequence: &[u8]| {
+			let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(&sequence.repeat(300)));
+			(screen.lines().count(), screen.lines().map(<[_]>::len).max().unwrap_or(0))
+		};
+
+		// Every sequence moves the cursor at most to the edge, only text makes the output grow further
+		assert_eq!(repeat(b"\x1B[65535Cx"), (1, 1299));
+		assert_eq!(repeat(b"\x1B[65535Bx").0, 300 * Screen::MAX_ROWS);
+		assert_eq!(repeat(b"\x1B[65535;1Hx").0, 65535);
+		assert_eq!(repeat(b"x\x1B[65535L").0, 300 * Screen::MAX_ROWS + 1);
+		assert_eq!(repeat(b"x\x1B[G\x1B[999@"), (1, Screen::MAX_COLUMNS));
+		// Inserting more blanks than fit pushes the text out of the line
+		assert_eq!(repeat(b"x\x1B[G\x1B[65535@"), (1, 0));
+		// Moving within long lines and far down in long output still works
+		assert_eq!(render(&[b"a".repeat(1500), b"\x1B[1200GX".to_vec()].concat()).find('X'), Some(1199));
+		assert_eq!(render(&[b"\n".repeat(1500), b"\x1B[1400;1HX".to_vec()].concat()).lines().nth(1399), Some("X"));
+		// Scrolling doesn't touch the output of a screen without a height
+		assert_eq!(render(b"1\n2\n3\x1B[2S4"), String::from("1\n2\n34"));
+		assert_eq!(render(b"1\n2\x1B[65535T3"), String::from("1\n23"));
+	}
+
+	#[test]
+	fn spinner_redraw_test() {
+		assert_eq!(render(b"Loading |\x1B[1D/\x1B[1D-\x1B[1D\\\x1B[1D\x1B[Kdone"), String::from("Loading done"));
+		assert_eq!(render(b"[#    ]\x1B[1G[##   ]\x1B[1G[#####]\n"), String::from("[#####]\n"));
+	}
+
+	#[test]
+	fn carriage_return_test() {
+		assert_eq!(render(b"10%\r50%\r100%\n"), String::from("100%\n"));
+		assert_eq!(render(b"Downloading 1/3\rDone\n"), String::from("Doneloading 1/3\n"));
+		assert_eq!(render(b"Downloading 1/3\r\x1B[KDone\n"), String::from("Done\n"));
+		assert_eq!(render(b"a\r\r\rb"), String::from("b"));
+		assert_eq!(render(b"one\ntwo\rTWO\nthree"), String::from("one\nTWO\nthree"));
+	}
+
+	#[test]
+	fn carriage_return_keep_frames_test() {
+		let keep_frames = |input: &[u8]| {
+			let mut screen = Screen::default().with_carriage_return(CarriageReturn::KeepFrames);
+			screen.apply_all(&TerminalOutputParser::parse_to_nodes(input));
+			screen_text(&screen)
+		};
+
+		assert_eq!(keep_frames(b"10%\r50%\r100%\n"), String::from("10%\n50%\n100%\n"));
+		assert_eq!(keep_frames(b"a\r\x1B[Kb\nc"), String::from("a\nb\nc"));
+		assert_eq!(keep_frames(b"\rx"), String::from("x"));
+	}
+
+	#[test]
+	fn backspace_test() {
+		assert_eq!(render(b"abc\x08d"), String::from("abd"));
+		assert_eq!(render(b"abc\x08\x08\x08\x08d"), String::from("dbc"));
+		assert_eq!(render(b"a\x08\x08b"), String::from("b"));
+	}
+
+	#[test]
+	fn overstrike_test() {
+		let styles = |input: &[u8]| {
+			let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input));
+			screen.lines().flat_map(|line| line.iter().map(|cell| cell.map(|cell| (cell.ch, cell.style)))).collect::<Vec<_>>()
+		};
+		let bold = StyleNode::from_ansi_node(&[vec![1]]);
+		let underline = StyleNode::from_ansi_node(&[vec![4]]);
+
+		assert_eq!(styles(b"H\x08Hi"), vec![Some(('H', bold)), Some(('i', StyleNode::default()))]);
+		assert_eq!(styles(b"_\x08H_\x08i"), vec![Some(('H', underline)), Some(('i', underline))]);
+		assert_eq!(styles(b"x\x08_"), vec![Some(('x', underline))]);
+		assert_eq!(styles(b"a\x08b"), vec![Some(('b', StyleNode::default()))]);
+
+		// Overwriting through cursor movement is not an overstrike
+		assert_eq!(styles(b"a\x1B[1Da"), vec![Some(('a', StyleNode::default()))]);
+	}
+
+	#[test]
+	fn hyperlink_test() {
+		let links = |input: &[u8]| {
+			let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input));
+			let cells =
+				screen.lines().flat_map(|line| line.iter().map(|cell| cell.and_then(|cell| cell.link))).collect::<Vec<_>>();
+			(cells, screen.links().to_vec())
+		};
+
+		assert_eq!(
+			links(b"a\x1B]8;;http://example.com\x07b\x1B]8;;\x07c"),
+			(
+				vec![None, Some(0), None],
+				vec![Hyperlink {
+					id: None,
+					url: String::from("http://example.com"),
+				}]
+			)
+		);
+
+		// Semicolons inside the url
+		assert_eq!(
+			links(b"\x1B]8;;http://example.com/?a=1;b=2\x1B\\x\x1B]8;;\x1B\\").1,
+			vec![Hyperlink {
+				id: None,
+				url: String::from("http://example.com/?a=1;b=2"),
+			}]
+		);
+
+		// Same id is the same link, no id is always a new link
+		assert_eq!(
+			links(b"\x1B]8;id=1;http://a\x07a\x1B]8;;\x07|\x1B]8;id=1;http://a\x07b\x1B]8;;http://a\x07c\x1B]8;;\x07"),
+			(
+				vec![Some(0), None, Some(0), Some(1)],
+				vec![
+					Hyperlink {
+						id: Some(String::from("1")),
+						url: String::from("http://a"),
+					},
+					Hyperlink {
+						id: None,
+						url: String::from("http://a"),
+					},
+				]
+			)
+		);
+
+		// Other OSC sequences don't touch links
+		assert_eq!(links(b"\x1B]0;title\x07a").0, vec![None]);
+	}
+
+	#[test]
+	fn title_test() {
+		let titles = |input: &[u8]| {
+			let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input));
+			(screen.title(TitleSelection::First).map(String::from), screen.title(TitleSelection::Last).map(String::from))
+		};
+
+		assert_eq!(titles(b"no title"), (None, None));
+		assert_eq!(
+			titles(b"\x1B]0;one\x07\x1B]2;two; with semicolon\x1B\\"),
+			(Some(String::from("one")), Some(String::from("two; with semicolon")))
+		);
+		assert_eq!(titles(b"\x1B]1;icon only\x07"), (None, None));
+		assert_eq!(titles(b"\x1B]2;one\x07\x1B]2;\x07"), (Some(String::from("one")), None));
+	}
+
+	#[test]
+	fn title_stack_test() {
+		let title = |input: &[u8]| {
+			Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input)).title(TitleSelection::Last).map(String::from)
+		};
+
+		assert_eq!(title(b"\x1B]2;shell\x07\x1B[22;0t\x1B]2;vim\x07\x1B[23;0t"), Some(String::from("shell")));
+		assert_eq!(title(b"\x1B]2;shell\x07\x1B[22t\x1B]2;vim\x07\x1B[23t"), Some(String::from("shell")));
+		assert_eq!(title(b"\x1B]2;shell\x07\x1B[22;2t\x1B]2;vim\x07\x1B[23;2t"), Some(String::from("shell")));
+		assert_eq!(title(b"\x1B[22t\x1B]2;vim\x07\x1B[23t"), None);
+		assert_eq!(title(b"\x1B]2;a\x07\x1B[22t\x1B]2;b\x07\x1B[22t\x1B]2;c\x07\x1B[23t"), Some(String::from("b")));
+
+		// Only the last ten pushes are kept
+		let mut input = b"\x1B]2;0\x07\x1B[22t".to_vec();
+		for n in 1..=20 {
+			input.extend(format!("\x1B]2;{n}\x07\x1B[22t").bytes());
+		}
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(&input));
+		assert_eq!(screen.title_stack.len(), 10);
+		assert_eq!(title(&[input.as_slice(), &b"\x1B[23t".repeat(10)].concat()), Some(String::from("11")));
+		assert_eq!(title(&[input.as_slice(), &b"\x1B[23t".repeat(11)].concat()), Some(String::from("11")));
+
+		// Icon only pushes and pops leave the window title alone
+		assert_eq!(title(b"\x1B]2;shell\x07\x1B[22;1t\x1B]2;vim\x07\x1B[23;1t"), Some(String::from("vim")));
+
+		// Popping an empty stack changes nothing
+		assert_eq!(title(b"\x1B]2;shell\x07\x1B[23t"), Some(String::from("shell")));
+	}
+
+	#[test]
+	fn palette_test() {
+		use crate::palette::Rgb;
+
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(
+			b"\x1B]4;1;rgb:12/34/56\x07\x1B]10;#eeeeee\x07\x1B]11;#111111\x1B\\\x1B]4;2;#fff\x07\x1B]104;2\x07",
+		));
+		assert_eq!(screen.palette().get(1), Rgb::new(0x12, 0x34, 0x56));
+		assert_eq!(screen.palette().get(2), Palette::default().get(2));
+		assert_eq!(screen.palette().foreground, Some(Rgb::new(0xee, 0xee, 0xee)));
+		assert_eq!(screen.palette().background, Some(Rgb::new(0x11, 0x11, 0x11)));
+
+		let mut initial = Palette::default();
+		initial.background = Some(Rgb::new(0, 0, 0x80));
+		let mut screen = Screen::default().with_palette(initial.clone());
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(b"\x1B]11;#fff\x07\x1B]111\x07"));
+		assert_eq!(screen.palette(), &initial);
+	}
+
+	#[test]
+	fn reduce_colors_test() {
+		let mut screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(
+			b"\x1B]4;3;#fa640a\x07\x1B[38;2;250;100;10;48;5;231;58;5;9mx",
+		));
+		let sgr = |screen: &Screen| screen.lines().next().unwrap()[0].unwrap().style.to_sgr();
+
+		screen.reduce_colors(ColorDepth::Palette256);
+		assert_eq!(sgr(&screen), "\x1B[0;38;5;208;48;5;231;58;5;9m");
+		// The 16 colors are the ones of the palette at the end of the stream
+		screen.reduce_colors(ColorDepth::Ansi16);
+		assert_eq!(sgr(&screen), "\x1B[0;33;107;58;5;9m");
+	}
+
+	#[test]
+	fn cell_style_test() {
+		let screen = Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(b"a\x1B[31mb\x1B[1D\x1B[0mc"));
+		let lines = screen.lines().collect::<Vec<_>>();
+		assert_eq!(
+			lines[0],
+			&[
+				Some(Cell {
+					ch: 'a',
+					style: StyleNode::default(),
+					link: None,
+				}),
+				Some(Cell {
+					ch: 'c',
+					style: StyleNode::default(),
+					link: None,
+				}),
+			]
+		);
+	}
+
+	#[test]
+	fn private_sequences_ignored_test() {
+		assert_eq!(render(b"a\x1B[?25lb\x1B[?1049hc"), String::from("abc"));
+	}
+
+	#[test]
+	fn viewport_test() {
+		let viewport = |screen: &Screen| {
+			screen
+				.viewport()
+				.iter()
+				.map(|line| line.iter().map(|cell| cell.map_or(' ', |cell| cell.ch)).collect::<String>())
+				.collect::<Vec<_>>()
+		};
+
+		let mut screen = Screen::new(Some(10), Some(3));
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(b"one"));
+		assert_eq!(viewport(&screen), vec!["one", "", ""]);
+
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(b"\ntwo\nthree\nfour"));
+		assert_eq!(viewport(&screen), vec!["two", "three", "four"]);
+		assert_eq!(screen_text(&screen), "one\ntwo\nthree\nfour");
+
+		assert_eq!(viewport(&Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(b"a\nb"))), vec!["a", "b"]);
+	}
+
+	#[test]
+	fn snapshot_test() {
+		let mut screen = Screen::new(Some(10), Some(2)).without_scrollback();
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(
+			b"\x1B]8;;https://a.example\x1B\\one\x1B]8;;\x1B\\\n\x1B]8;;https://b.example\x1B\\two\x1B]8;;\x1B\\\nthree\x1B[A",
+		));
+		assert_eq!(screen_text(&screen), "two\nthree");
+
+		let snapshot = screen.snapshot();
+		assert_eq!(screen_text(&snapshot), "two\nthree");
+		assert_eq!(snapshot.links().len(), 1);
+		assert_eq!(snapshot.links()[0].url, "https://b.example");
+		assert_eq!(snapshot.lines().next().unwrap()[0].unwrap().link, Some(0));
+
+		// The cursor keeps its place in view
+		let mut snapshot = snapshot;
+		snapshot.apply_all(&TerminalOutputParser::parse_to_nodes(b"\rTWO"));
+		assert_eq!(screen_text(&snapshot), "TWO\nthree");
+	}
+}
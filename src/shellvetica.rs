@@ -1,3 +1,5 @@
+use crate::html::escape_char;
+
 // TODO: add BgColors
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Color {
@@ -36,7 +38,10 @@ pub enum Token {
 impl std::fmt::Display for Token {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		match self {
-			Token::Text(c) => write!(f, "{c}"),
+			Token::Text(c) => match escape_char(*c) {
+				Some(entity) => write!(f, "{entity}"),
+				None => write!(f, "{c}"),
+			},
 			Token::Color(color) => write!(f, "<span style=\"color:{color}\">"),
 			Token::Close => write!(f, "</span>"),
 		}
@@ -173,6 +178,7 @@ impl Shellvetica {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod test {
 	use super::*;
 
@@ -409,6 +415,18 @@ mod test {
 		);
 	}
 
+	#[test]
+	fn export_escape_test() {
+		assert_eq!(
+			Shellvetica::convert("\x1B[31mVec<T>\x1B[0m && \"x\"").export(),
+			String::from("<span style=\"color:red\">Vec&lt;T&gt;</span> &amp;&amp; &quot;x&quot;")
+		);
+		assert_eq!(
+			Shellvetica::convert("<b onclick='x()'>&amp</b>").export(),
+			String::from("&lt;b onclick=&#39;x()&#39;&gt;&amp;amp&lt;/b&gt;")
+		);
+	}
+
 	// #[test]
 	// fn optimize_ast_overwritten_colors_test() {
 	// 	assert_eq!(
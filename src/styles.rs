@@ -1,4 +1,11 @@
+use crate::{
+	json::Json,
+	palette::{ColorDepth, Palette, Rgb},
+};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum EightBitColor {
 	Black,
 	Red,
@@ -27,6 +34,8 @@ impl EightBitColor {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Color {
 	/// Standard 8 colors (30-37, 40-47)
 	Standard(EightBitColor),
@@ -38,7 +47,21 @@ pub enum Color {
 	Rgb { r: u8, g: u8, b: u8 },
 }
 
+impl Color {
+	/// The index of the color in a 256 color palette or None for true colors
+	pub fn palette_index(&self) -> Option<u8> {
+		match self {
+			Color::Standard(color) => Some(*color as u8),
+			Color::Bright(color) => Some(8 + *color as u8),
+			Color::Palette(index) => Some(*index),
+			Color::Rgb { .. } => None,
+		}
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum UnderlineStyle {
 	Single,
 	Double,
@@ -48,6 +71,8 @@ pub enum UnderlineStyle {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Font {
 	One,
 	Two,
@@ -78,7 +103,43 @@ impl Font {
 	}
 }
 
+/// A CSS declaration and the class name (without prefix) that stands for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRule {
+	pub class: String,
+	pub css: String,
+}
+
+impl StyleRule {
+	const ORDER: [&str; 10] = [
+		"bold",
+		"dim",
+		"italic",
+		"underline",
+		"ul-",
+		"blink",
+		"rapid-blink",
+		"strike",
+		"fg-",
+		"bg-",
+	];
+
+	pub fn new(class: &str, css: &str) -> Self {
+		Self {
+			class: class.to_string(),
+			css: css.to_string(),
+		}
+	}
+
+	/// Where the rule goes in a stylesheet so it cascades like inline styles do,
+	/// e.g. text-decoration-color has to come after the text-decoration shorthand
+	pub fn order(&self) -> usize {
+		Self::ORDER.iter().position(|class| self.class.starts_with(class)).unwrap_or(Self::ORDER.len())
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StyleNode {
 	bold: bool,
 	dim: bool,
@@ -100,7 +161,9 @@ pub struct StyleNode {
 	overlined: bool,
 	foreground: Option<Color>,
 	background: Option<Color>,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	fg_bright_from_bold: bool,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	bg_bright_from_bold: bool,
 }
 
@@ -109,32 +172,38 @@ impl StyleNode {
 
 	pub fn from_ansi_node(params: &[Vec<u16>]) -> Self {
 		let mut result = Self::default();
+		result.apply_sgr(params);
+		result
+	}
 
-		for param_group in params {
+	pub fn apply_sgr(&mut self, params: &[Vec<u16>]) {
+		let mut groups = params.iter();
+
+		while let Some(param_group) = groups.next() {
 			match param_group.as_slice() {
 				// Reset all
-				[0, ..] => result = Self::default(),
+				[0, ..] => *self = Self::default(),
 
 				// Styles
 				[1, ..] => {
-					result.bold = true;
+					self.bold = true;
 					// If we already have a standard foreground color, upgrade it to bright
-					if let Some(Color::Standard(n)) = result.foreground {
-						result.foreground = Some(Color::Bright(n));
-						result.fg_bright_from_bold = true;
+					if let Some(Color::Standard(n)) = self.foreground {
+						self.foreground = Some(Color::Bright(n));
+						self.fg_bright_from_bold = true;
 					}
 					// Upgrade standard background to bright
-					if let Some(Color::Standard(n)) = result.background {
-						result.background = Some(Color::Bright(n));
-						result.bg_bright_from_bold = true;
+					if let Some(Color::Standard(n)) = self.background {
+						self.background = Some(Color::Bright(n));
+						self.bg_bright_from_bold = true;
 					}
 				},
-				[2, ..] => result.dim = true,
-				[3, ..] => result.italic = true,
+				[2, ..] => self.dim = true,
+				[3, ..] => self.italic = true,
 
 				// Underline with style (4:2 becomes [4, 2])
 				[4, style, ..] => {
-					result.underline = match style {
+					self.underline = match style {
 						0 => None,
 						1 => Some(UnderlineStyle::Single),
 						2 => Some(UnderlineStyle::Double),
@@ -144,267 +213,680 @@ impl StyleNode {
 						_ => Some(UnderlineStyle::Single),
 					};
 				},
-				[4] => result.underline = Some(UnderlineStyle::Single),
+				[4] => self.underline = Some(UnderlineStyle::Single),
 
-				[5, ..] => result.blink = true,
-				[6, ..] => result.rapid_blink = true,
-				[7, ..] => result.reverse = true,
-				[8, ..] => result.hidden = true,
-				[9, ..] => result.strikethrough = true,
-				[10, ..] => result.font = Font::from_u8(0),
-				[n @ 11..=19, ..] => result.font = Font::from_u8((n - 10) as u8),
-				[20, ..] => result.fraktur = true,
+				[5, ..] => self.blink = true,
+				[6, ..] => self.rapid_blink = true,
+				[7, ..] => self.reverse = true,
+				[8, ..] => self.hidden = true,
+				[9, ..] => self.strikethrough = true,
+				[10, ..] => self.font = Font::from_u8(0),
+				[n @ 11..=19, ..] => self.font = Font::from_u8((n - 10) as u8),
+				[20, ..] => self.fraktur = true,
 
 				// Reset individual attributes
 				[21 | 22, ..] => {
-					result.bold = false;
-					result.dim = false;
+					self.bold = false;
+					self.dim = false;
 					// Downgrade bright colors if they came from bold
-					if result.fg_bright_from_bold {
-						if let Some(Color::Bright(n)) = result.foreground {
-							result.foreground = Some(Color::Standard(n));
-							result.fg_bright_from_bold = false;
-						}
+					if self.fg_bright_from_bold
+						&& let Some(Color::Bright(n)) = self.foreground
+					{
+						self.foreground = Some(Color::Standard(n));
+						self.fg_bright_from_bold = false;
 					}
-					if result.bg_bright_from_bold {
-						if let Some(Color::Bright(n)) = result.background {
-							result.background = Some(Color::Standard(n));
-							result.bg_bright_from_bold = false;
-						}
+					if self.bg_bright_from_bold
+						&& let Some(Color::Bright(n)) = self.background
+					{
+						self.background = Some(Color::Standard(n));
+						self.bg_bright_from_bold = false;
 					}
 				},
-				[23, ..] => result.italic = false,
-				[24, ..] => result.underline = None,
+				[23, ..] => self.italic = false,
+				[24, ..] => self.underline = None,
 				[25, ..] => {
-					result.blink = false;
-					result.rapid_blink = false;
+					self.blink = false;
+					self.rapid_blink = false;
 				},
-				[26, ..] => result.proportional_spacing = true,
-				[27, ..] => result.reverse = false,
-				[28, ..] => result.hidden = false,
-				[29, ..] => result.strikethrough = false,
+				[26, ..] => self.proportional_spacing = true,
+				[27, ..] => self.reverse = false,
+				[28, ..] => self.hidden = false,
+				[29, ..] => self.strikethrough = false,
 
 				// Standard foreground colors
 				[n @ 30..=37, ..] => {
 					let color_index = (n - 30) as u8;
-					result.foreground = Some(if result.bold {
-						result.fg_bright_from_bold = true;
+					self.foreground = Some(if self.bold {
 						Color::Bright(EightBitColor::from_u8(color_index))
 					} else {
 						Color::Standard(EightBitColor::from_u8(color_index))
 					});
+					self.fg_bright_from_bold = self.bold;
 				},
 
 				// Extended foreground colors
-				[38, 5, palette, ..] => {
-					result.foreground = Some(Color::Palette(*palette as u8));
+				[38, rest @ ..] if !rest.is_empty() => {
+					if let Some(color) = Self::extended_color(rest) {
+						self.foreground = Some(color);
+						self.fg_bright_from_bold = false;
+					}
 				},
-				[38, 2, r, g, b, ..] => {
-					result.foreground = Some(Color::Rgb {
-						r: (*r).min(255) as u8,
-						g: (*g).min(255) as u8,
-						b: (*b).min(255) as u8,
-					});
+				[38] => {
+					if let Some(color) = Self::extended_color_from_groups(&mut groups) {
+						self.foreground = Some(color);
+						self.fg_bright_from_bold = false;
+					}
 				},
 
 				// Default foreground
-				[39, ..] => result.foreground = None,
+				[39, ..] => {
+					self.foreground = None;
+					self.fg_bright_from_bold = false;
+				},
 
 				// Standard background colors
 				[n @ 40..=47, ..] => {
 					let color_index = (n - 40) as u8;
-					result.background = Some(if result.bold {
-						result.bg_bright_from_bold = true;
+					self.background = Some(if self.bold {
 						Color::Bright(EightBitColor::from_u8(color_index))
 					} else {
 						Color::Standard(EightBitColor::from_u8(color_index))
 					});
+					self.bg_bright_from_bold = self.bold;
 				},
 
 				// Extended background colors
-				[48, 5, palette, ..] => {
-					result.background = Some(Color::Palette(*palette as u8));
+				[48, rest @ ..] if !rest.is_empty() => {
+					if let Some(color) = Self::extended_color(rest) {
+						self.background = Some(color);
+						self.bg_bright_from_bold = false;
+					}
 				},
-				[48, 2, r, g, b, ..] => {
-					result.background = Some(Color::Rgb {
-						r: (*r).min(255) as u8,
-						g: (*g).min(255) as u8,
-						b: (*b).min(255) as u8,
-					});
+				[48] => {
+					if let Some(color) = Self::extended_color_from_groups(&mut groups) {
+						self.background = Some(color);
+						self.bg_bright_from_bold = false;
+					}
 				},
 
 				// Default background
-				[49, ..] => result.background = None,
+				[49, ..] => {
+					self.background = None;
+					self.bg_bright_from_bold = false;
+				},
 
 				// Legacy styles
-				[50, ..] => result.proportional_spacing = false,
-				[51, ..] => result.framed = true,
-				[52, ..] => result.encircled = true,
-				[53, ..] => result.overlined = true,
+				[50, ..] => self.proportional_spacing = false,
+				[51, ..] => self.framed = true,
+				[52, ..] => self.encircled = true,
+				[53, ..] => self.overlined = true,
 				[54, ..] => {
-					result.framed = false;
-					result.encircled = false;
+					self.framed = false;
+					self.encircled = false;
 				},
-				[55, ..] => result.overlined = false,
+				[55, ..] => self.overlined = false,
 
 				// Extended underline colors
-				[58, 5, palette, ..] => {
-					result.underline_color = Some(Color::Palette(*palette as u8));
+				[58, rest @ ..] if !rest.is_empty() => {
+					if let Some(color) = Self::extended_color(rest) {
+						self.underline_color = Some(color);
+					}
 				},
-				[58, 2, r, g, b, ..] => {
-					result.underline_color = Some(Color::Rgb {
-						r: (*r).min(255) as u8,
-						g: (*g).min(255) as u8,
-						b: (*b).min(255) as u8,
-					});
+				[58] => {
+					if let Some(color) = Self::extended_color_from_groups(&mut groups) {
+						self.underline_color = Some(color);
+					}
 				},
-				[59, ..] => result.underline_color = None,
+				[59, ..] => self.underline_color = None,
 
 				// Sub/superscript
 				[73, ..] => {
-					result.superscript = true;
-					result.subscript = false;
+					self.superscript = true;
+					self.subscript = false;
 				},
 				[74, ..] => {
-					result.subscript = true;
-					result.superscript = false;
+					self.subscript = true;
+					self.superscript = false;
 				},
 				[75, ..] => {
-					result.subscript = false;
-					result.superscript = false;
+					self.subscript = false;
+					self.superscript = false;
 				},
 
 				// Bright foreground colors (direct)
 				[n @ 90..=97, ..] => {
-					result.foreground = Some(Color::Bright(EightBitColor::from_u8((n - 90) as u8)));
+					self.foreground = Some(Color::Bright(EightBitColor::from_u8((n - 90) as u8)));
+					self.fg_bright_from_bold = false;
 				},
 
 				// Bright background colors (direct)
 				[n @ 100..=107, ..] => {
-					result.background = Some(Color::Bright(EightBitColor::from_u8((n - 100) as u8)));
+					self.background = Some(Color::Bright(EightBitColor::from_u8((n - 100) as u8)));
+					self.bg_bright_from_bold = false;
 				},
 
 				_ => {}, // Unknown SGR code, ignore
 			}
 		}
+	}
 
-		result
+	// Bookkeeping flags like fg_bright_from_bold don't change how a style looks so we ignore them when comparing
+	pub fn effective(&self) -> Self {
+		Self {
+			fg_bright_from_bold: false,
+			bg_bright_from_bold: false,
+			..*self
+		}
 	}
 
-	fn standard_color_to_hex(color: &EightBitColor) -> &'static str {
-		match color {
-			EightBitColor::Black => "#000",
-			EightBitColor::Red => "#cd0000",
-			EightBitColor::Green => "#00cd00",
-			EightBitColor::Yellow => "#cdcd00",
-			EightBitColor::Blue => "#00e",
-			EightBitColor::Magenta => "#cd00cd",
-			EightBitColor::Cyan => "#00cdcd",
-			EightBitColor::White => "#e5e5e5",
+	/// Turns every color of the style into the closest one the palette can show with the given color depth
+	pub fn reduce_colors(&mut self, palette: &Palette, depth: ColorDepth) {
+		for color in [&mut self.foreground, &mut self.background, &mut self.underline_color].into_iter().flatten() {
+			*color = palette.reduce(*color, depth);
 		}
 	}
 
-	fn bright_color_to_hex(color: &EightBitColor) -> &'static str {
-		match color {
-			EightBitColor::Black => "#7f7f7f",
-			EightBitColor::Red => "#f00",
-			EightBitColor::Green => "#0f0",
-			EightBitColor::Yellow => "#ff0",
-			EightBitColor::Blue => "#5c5cff",
-			EightBitColor::Magenta => "#f0f",
-			EightBitColor::Cyan => "#0ff",
-			EightBitColor::White => "#fff",
+	pub fn is_default(&self) -> bool {
+		self.effective() == Self::default()
+	}
+
+	pub fn bold(&self) -> bool {
+		self.bold
+	}
+
+	pub fn dim(&self) -> bool {
+		self.dim
+	}
+
+	pub fn italic(&self) -> bool {
+		self.italic
+	}
+
+	pub fn underline(&self) -> Option<UnderlineStyle> {
+		self.underline
+	}
+
+	pub fn underline_color(&self) -> Option<Color> {
+		self.underline_color
+	}
+
+	pub fn strikethrough(&self) -> bool {
+		self.strikethrough
+	}
+
+	pub fn hidden(&self) -> bool {
+		self.hidden
+	}
+
+	/// The foreground and background with reverse applied where None means the default color
+	pub fn resolved_colors(&self, palette: &Palette) -> (Option<Rgb>, Option<Rgb>) {
+		let foreground = self.foreground.map(|color| palette.resolve(&color));
+		let background = self.background.map(|color| palette.resolve(&color));
+
+		if self.reverse {
+			(background.or(palette.background), foreground.or(palette.foreground))
+		} else {
+			(foreground, background)
 		}
 	}
 
-	fn append_color(html: &mut String, color: &Color) {
-		match color {
-			Color::Standard(color) => {
-				html.push_str(Self::standard_color_to_hex(&color));
-			},
-			Color::Bright(color) => {
-				html.push_str(Self::bright_color_to_hex(&color));
-			},
-			Color::Palette(color) => match color {
-				0..=7 => html.push_str(Self::standard_color_to_hex(&EightBitColor::from_u8(*color))),
-				8..=15 => html.push_str(Self::bright_color_to_hex(&EightBitColor::from_u8(color - 8))),
-				16..=231 => {
-					let n = color - 16;
-					let r = (n / 36) * 51;
-					let g = ((n % 36) / 6) * 51;
-					let b = (n % 6) * 51;
-					Self::push_hex_rgb(html, r, g, b);
-				},
-				232..=255 => {
-					let gray = 8 + (color - 232) * 10;
-					Self::push_hex_rgb(html, gray, gray, gray);
-				},
-			},
-			Color::Rgb { r, g, b } => {
-				Self::push_hex_rgb(html, *r, *g, *b);
+	// Colon form, e.g. 38:5:196, 38:2:255:0:0 or 38:2::255:0:0 with the (ignored) color space id
+	fn extended_color(params: &[u16]) -> Option<Color> {
+		match params {
+			[5, palette, ..] => Some(Color::Palette((*palette).min(255) as u8)),
+			[2, _, r, g, b, ..] | [2, r, g, b] => Some(Color::Rgb {
+				r: (*r).min(255) as u8,
+				g: (*g).min(255) as u8,
+				b: (*b).min(255) as u8,
+			}),
+			_ => None,
+		}
+	}
+
+	// Semicolon form, e.g. 38;5;196 or 38;2;255;0;0 where every value arrives as its own param group
+	fn extended_color_from_groups(groups: &mut std::slice::Iter<Vec<u16>>) -> Option<Color> {
+		let mut next = || groups.next().and_then(|group| group.first().copied());
+
+		match next()? {
+			5 => Some(Color::Palette(next()?.min(255) as u8)),
+			2 => {
+				let r = next()?;
+				let g = next()?;
+				let b = next()?;
+				Self::extended_color(&[2, r, g, b])
 			},
+			_ => None,
+		}
+	}
+
+	/// The SGR sequence that switches any style to this one, starting with a reset
+	pub fn to_sgr(&self) -> String {
+		let mut params = vec![String::from("0")];
+		let flags = [
+			(self.bold, "1"),
+			(self.dim, "2"),
+			(self.italic, "3"),
+			(self.blink, "5"),
+			(self.rapid_blink, "6"),
+			(self.reverse, "7"),
+			(self.hidden, "8"),
+			(self.strikethrough, "9"),
+			(self.fraktur, "20"),
+			(self.proportional_spacing, "26"),
+			(self.framed, "51"),
+			(self.encircled, "52"),
+			(self.overlined, "53"),
+			(self.superscript, "73"),
+			(self.subscript, "74"),
+		];
+		params.extend(flags.iter().filter(|(set, _)| *set).map(|(_, param)| param.to_string()));
+
+		match self.underline {
+			Some(UnderlineStyle::Single) => params.push(String::from("4")),
+			Some(UnderlineStyle::Double) => params.push(String::from("4:2")),
+			Some(UnderlineStyle::Curly) => params.push(String::from("4:3")),
+			Some(UnderlineStyle::Dotted) => params.push(String::from("4:4")),
+			Some(UnderlineStyle::Dashed) => params.push(String::from("4:5")),
+			None => {},
+		}
+		if let Some(font) = self.font {
+			params.push((11 + font as u8).to_string());
+		}
+		if let Some(color) = self.foreground {
+			params.push(Self::sgr_color(color, 30, self.fg_bright_from_bold));
+		}
+		if let Some(color) = self.background {
+			params.push(Self::sgr_color(color, 40, self.bg_bright_from_bold));
+		}
+		if let Some(color) = self.underline_color {
+			params.push(match color {
+				Color::Standard(color) => format!("58;5;{}", color as u8),
+				Color::Bright(color) => format!("58;5;{}", 8 + color as u8),
+				_ => Self::sgr_color(color, 50, false),
+			});
+		}
+
+		format!("\x1B[{}m", params.join(";"))
+	}
+
+	/// Moves this style to `target` and returns the shortest SGR sequence that does the same in a terminal,
+	/// which is empty when both already look the same
+	pub fn transition_to(&mut self, target: &StyleNode) -> String {
+		if self.effective() == target.effective() {
+			return String::new();
+		}
+
+		let mut state = *self;
+		let mut params = Vec::new();
+		let mut push = |state: &mut StyleNode, param: String| {
+			state.apply_sgr(&Self::sgr_groups(&param));
+			params.push(param);
 		};
+
+		// Bold and dim, blink and rapid blink as well as framed and encircled can only be turned off together
+		let pairs = [
+			((state.bold, target.bold), (state.dim, target.dim), ["22", "1", "2"]),
+			((state.blink, target.blink), (state.rapid_blink, target.rapid_blink), ["25", "5", "6"]),
+			((state.framed, target.framed), (state.encircled, target.encircled), ["54", "51", "52"]),
+		];
+		for ((first, target_first), (second, target_second), [off, first_on, second_on]) in pairs {
+			let reset = (first && !target_first) || (second && !target_second);
+			if reset {
+				push(&mut state, String::from(off));
+			}
+			if target_first && (reset || !first) {
+				push(&mut state, String::from(first_on));
+			}
+			if target_second && (reset || !second) {
+				push(&mut state, String::from(second_on));
+			}
+		}
+
+		let flags = [
+			(state.italic, target.italic, "3", "23"),
+			(state.reverse, target.reverse, "7", "27"),
+			(state.hidden, target.hidden, "8", "28"),
+			(state.strikethrough, target.strikethrough, "9", "29"),
+			(state.proportional_spacing, target.proportional_spacing, "26", "50"),
+			(state.overlined, target.overlined, "53", "55"),
+		];
+		for (set, target_set, on, off) in flags {
+			if set != target_set {
+				push(&mut state, String::from(if target_set { on } else { off }));
+			}
+		}
+		// Fraktur has no code of its own to turn it off
+		if target.fraktur && !state.fraktur {
+			push(&mut state, String::from("20"));
+		}
+
+		if state.underline != target.underline {
+			push(
+				&mut state,
+				String::from(match target.underline {
+					Some(UnderlineStyle::Single) => "4",
+					Some(UnderlineStyle::Double) => "4:2",
+					Some(UnderlineStyle::Curly) => "4:3",
+					Some(UnderlineStyle::Dotted) => "4:4",
+					Some(UnderlineStyle::Dashed) => "4:5",
+					None => "24",
+				}),
+			);
+		}
+		if state.font != target.font {
+			push(&mut state, (10 + target.font.map_or(0, |font| font as u8 + 1)).to_string());
+		}
+		if (state.superscript, state.subscript) != (target.superscript, target.subscript) {
+			let param = match (target.superscript, target.subscript) {
+				(true, _) => "73",
+				(_, true) => "74",
+				_ => "75",
+			};
+			push(&mut state, String::from(param));
+		}
+
+		if state.foreground != target.foreground {
+			let param =
+				target.foreground.map_or(String::from("39"), |color| Self::sgr_color(color, 30, target.fg_bright_from_bold));
+			push(&mut state, param);
+		}
+		if state.background != target.background {
+			let param =
+				target.background.map_or(String::from("49"), |color| Self::sgr_color(color, 40, target.bg_bright_from_bold));
+			push(&mut state, param);
+		}
+		if state.underline_color != target.underline_color {
+			let param = match target.underline_color {
+				Some(Color::Standard(color)) => format!("58;5;{}", color as u8),
+				Some(Color::Bright(color)) => format!("58;5;{}", 8 + color as u8),
+				Some(color) => Self::sgr_color(color, 50, false),
+				None => String::from("59"),
+			};
+			push(&mut state, param);
+		}
+
+		// Starting over is shorter when most of the style changes and the only way to turn some things off
+		let reset = target.to_sgr();
+		let changes = format!("\x1B[{}m", params.join(";"));
+		if state.effective() == target.effective() && changes.len() < reset.len() {
+			*self = state;
+			changes
+		} else {
+			*self = StyleNode::from_ansi_node(&Self::sgr_groups(&reset[2..reset.len() - 1]));
+			reset
+		}
 	}
 
-	pub fn to_html(&mut self) -> String {
-		let mut html = String::with_capacity(200);
+	// The parameters of a sequence like "1;4:3" in the groups the parser produces
+	fn sgr_groups(params: &str) -> Vec<Vec<u16>> {
+		params.split(';').map(|group| group.split(':').filter_map(|n| n.parse().ok()).collect()).collect()
+	}
+
+	// Bold turns standard colors bright on its own so those are written as the standard color they were set as
+	fn sgr_color(color: Color, base: u8, bright_from_bold: bool) -> String {
+		match color {
+			Color::Standard(color) => (base + color as u8).to_string(),
+			Color::Bright(color) if bright_from_bold => (base + color as u8).to_string(),
+			Color::Bright(color) => (base + 60 + color as u8).to_string(),
+			Color::Palette(index) => format!("{};5;{index}", base + 8),
+			Color::Rgb { r, g, b } => format!("{};2;{r};{g};{b}", base + 8),
+		}
+	}
+
+	pub fn append_color(html: &mut String, color: &Color, palette: &Palette) {
+		Self::append_rgb(html, palette.resolve(color));
+	}
 
-		let tag = if self.subscript {
+	pub fn append_rgb(html: &mut String, color: Rgb) {
+		Self::push_hex_rgb(html, color.r, color.g, color.b);
+	}
+
+	fn tag(&self) -> &'static str {
+		if self.subscript {
 			"sub"
 		} else if self.superscript {
 			"sup"
 		} else {
 			"span"
-		};
+		}
+	}
+
+	pub fn to_html(&self) -> String {
+		self.to_html_with_palette(&Palette::default())
+	}
+
+	pub fn to_html_with_palette(&self, palette: &Palette) -> String {
+		self.to_html_with_rules(&self.rules(palette, None))
+	}
 
-		html.push_str("<");
-		html.push_str(tag);
+	/// Opens a tag with the given rules as inline styles
+	pub fn to_html_with_rules(&self, rules: &[StyleRule]) -> String {
+		let mut html = String::with_capacity(200);
+
+		html.push('<');
+		html.push_str(self.tag());
 		html.push_str(" style=\"");
+		for rule in rules {
+			html.push_str(&rule.css);
+		}
+		html.push_str("\">");
+		html
+	}
+
+	/// Like `to_html_with_rules` but with classes instead of inline styles, e.g. `<span class="sv-bold sv-fg-1">`
+	pub fn to_html_with_classes(&self, rules: &[StyleRule], prefix: &str) -> String {
+		let mut html = String::with_capacity(100);
+
+		html.push('<');
+		html.push_str(self.tag());
+		if !rules.is_empty() {
+			html.push_str(" class=\"");
+			for (index, rule) in rules.iter().enumerate() {
+				if index > 0 {
+					html.push(' ');
+				}
+				html.push_str(prefix);
+				html.push_str(&rule.class);
+			}
+			html.push('"');
+		}
+		html.push('>');
+		html
+	}
+
+	/// Every attribute of the style under the same keys the serde feature uses, except that colors are resolved to hex
+	/// next to the palette index they came from. Reverse is already applied to the colors, which also swaps in the
+	/// default colors when they are known, so it is always false to keep it from being applied twice.
+	pub(crate) fn to_json(self, palette: &Palette) -> Json {
+		let (foreground, background) = if self.reverse {
+			(self.background, self.foreground)
+		} else {
+			(self.foreground, self.background)
+		};
+		let (foreground_rgb, background_rgb) = self.resolved_colors(palette);
+		let color = |color: Option<Color>, rgb: Option<Rgb>| match rgb {
+			Some(rgb) => Json::Object(vec![
+				(String::from("hex"), Json::String(rgb.to_hex())),
+				(
+					String::from("index"),
+					color.and_then(|color| color.palette_index()).map_or(Json::Null, |index| Json::Number(index as f64)),
+				),
+			]),
+			None => Json::Null,
+		};
+		// Alternative fonts 1 to 9 from SGR 11 to 19
+		let font = self.font.map_or(Json::Null, |font| {
+			Json::String(String::from(match font {
+				Font::One => "one",
+				Font::Two => "two",
+				Font::Three => "three",
+				Font::Four => "four",
+				Font::Five => "five",
+				Font::Six => "six",
+				Font::Seven => "seven",
+				Font::Eight => "eight",
+				Font::Nine => "nine",
+			}))
+		});
+		let underline = self.underline.map_or(Json::Null, |underline| {
+			Json::String(String::from(match underline {
+				UnderlineStyle::Single => "single",
+				UnderlineStyle::Double => "double",
+				UnderlineStyle::Curly => "curly",
+				UnderlineStyle::Dotted => "dotted",
+				UnderlineStyle::Dashed => "dashed",
+			}))
+		});
+
+		let mut entries = vec![
+			(String::from("foreground"), color(foreground, foreground_rgb)),
+			(String::from("background"), color(background, background_rgb)),
+			(String::from("underline"), underline),
+			(
+				String::from("underline_color"),
+				color(self.underline_color, self.underline_color.map(|color| palette.resolve(&color))),
+			),
+			(String::from("font"), font),
+		];
+		for (name, value) in [
+			("bold", self.bold),
+			("dim", self.dim),
+			("italic", self.italic),
+			("blink", self.blink),
+			("rapid_blink", self.rapid_blink),
+			("reverse", false),
+			("hidden", self.hidden),
+			("strikethrough", self.strikethrough),
+			("superscript", self.superscript),
+			("subscript", self.subscript),
+			("overlined", self.overlined),
+			("framed", self.framed),
+			("encircled", self.encircled),
+			("fraktur", self.fraktur),
+			("proportional_spacing", self.proportional_spacing),
+		] {
+			entries.push((String::from(name), Json::Bool(value)));
+		}
+
+		Json::Object(entries)
+	}
+
+	/// Inline styles limited to what email clients support. Colors are resolved with dim blended in since opacity is
+	/// ignored, underline and strikethrough share one text-decoration and animations are dropped.
+	/// Text with a color goes in a <font> tag so it keeps the color where style attributes are stripped.
+	/// The palette needs default colors to blend and reverse against. Styles with nothing an email can show, like blink
+	/// on its own, are an empty string so no tag is written for them.
+	pub fn to_html_for_email(&self, palette: &Palette) -> String {
+		let (foreground, background) = self.email_colors(palette);
+		let mut css = String::new();
+
+		if self.bold {
+			css.push_str("font-weight:bold;");
+		}
+		if self.italic {
+			css.push_str("font-style:italic;");
+		}
+		let decorations = [
+			(self.underline.is_some(), "underline"),
+			(self.strikethrough, "line-through"),
+		]
+		.iter()
+		.filter(|(set, _)| *set)
+		.map(|(_, decoration)| *decoration)
+		.collect::<Vec<_>>();
+		if !decorations.is_empty() {
+			css.push_str(&format!("text-decoration:{};", decorations.join(" ")));
+		}
+		if let Some(color) = foreground {
+			css.push_str(&format!("color:{};", color.to_hex()));
+		}
+		if let Some(color) = background {
+			css.push_str(&format!("background-color:{};", color.to_hex()));
+		}
+
+		let tag = Self::email_tag(self.tag(), foreground);
+		if tag == "span" && css.is_empty() {
+			return String::new();
+		}
+		let mut html = format!("<{tag}");
+		if tag == "font"
+			&& let Some(color) = foreground
+		{
+			html.push_str(&format!(" color=\"{}\"", color.to_hex()));
+		}
+		if !css.is_empty() {
+			html.push_str(&format!(" style=\"{css}\""));
+		}
+		html.push('>');
+		html
+	}
+
+	pub fn to_html_close_for_email(&self, palette: &Palette) -> String {
+		if self.to_html_for_email(palette).is_empty() {
+			return String::new();
+		}
+		format!("</{}>", Self::email_tag(self.tag(), self.email_colors(palette).0))
+	}
+
+	fn email_colors(&self, palette: &Palette) -> (Option<Rgb>, Option<Rgb>) {
+		let (mut foreground, background) = self.resolved_colors(palette);
+		if self.dim
+			&& let (Some(text), Some(fill)) = (foreground.or(palette.foreground), background.or(palette.background))
+		{
+			foreground = Some(text.blend(fill));
+		}
+		(foreground, background)
+	}
+
+	fn email_tag(tag: &'static str, foreground: Option<Rgb>) -> &'static str {
+		match tag {
+			"span" if foreground.is_some() => "font",
+			tag => tag,
+		}
+	}
+
+	/// The CSS declarations of this style each with the class name that stands for it.
+	/// With a `variables` prefix the 16 ANSI colors and the default colors are CSS custom properties like var(--sv-red).
+	pub fn rules(&self, palette: &Palette, variables: Option<&str>) -> Vec<StyleRule> {
+		let mut rules = Vec::new();
 
 		if self.bold {
-			html.push_str("font-weight:bold;");
+			rules.push(StyleRule::new("bold", "font-weight:bold;"));
 		}
 
 		if self.dim {
-			html.push_str("opacity:.5;");
+			rules.push(StyleRule::new("dim", "opacity:.5;"));
 		}
 
 		if self.italic {
-			html.push_str("font-style:italic;");
+			rules.push(StyleRule::new("italic", "font-style:italic;"));
 		}
 
 		if let Some(underline) = self.underline {
-			match underline {
-				UnderlineStyle::Single => html.push_str("text-decoration:underline;"),
-				UnderlineStyle::Double => html.push_str("text-decoration:underline double;"),
-				UnderlineStyle::Curly => html.push_str("text-decoration:underline wavy;"),
-				UnderlineStyle::Dotted => html.push_str("text-decoration:underline dotted;"),
-				UnderlineStyle::Dashed => html.push_str("text-decoration:underline dashed;"),
-			}
+			rules.push(match underline {
+				UnderlineStyle::Single => StyleRule::new("underline", "text-decoration:underline;"),
+				UnderlineStyle::Double => StyleRule::new("underline-double", "text-decoration:underline double;"),
+				UnderlineStyle::Curly => StyleRule::new("underline-curly", "text-decoration:underline wavy;"),
+				UnderlineStyle::Dotted => StyleRule::new("underline-dotted", "text-decoration:underline dotted;"),
+				UnderlineStyle::Dashed => StyleRule::new("underline-dashed", "text-decoration:underline dashed;"),
+			});
 		}
 
 		if let Some(underline_color) = self.underline_color {
-			html.push_str("text-decoration-color:");
-			Self::append_color(&mut html, &underline_color);
-			html.push(';');
+			rules.push(Self::color_rule("ul", "text-decoration-color", &underline_color, palette, variables));
 		}
 
+		// The blink @keyframes come with the stylesheet of the HtmlRenderer
 		if self.blink {
-			// @keyframes blink {
-			// 	50% { opacity: 0; }
-			// }
-			html.push_str("animation:blink 1s step-start infinite;");
+			rules.push(StyleRule::new("blink", "animation:blink 1s step-start infinite;"));
 		}
 
 		if self.rapid_blink {
-			// @keyframes blink {
-			// 	50% { opacity: 0; }
-			// }
-			html.push_str("animation:blink .5s step-start infinite;");
+			rules.push(StyleRule::new("rapid-blink", "animation:blink .5s step-start infinite;"));
 		}
 
 		if self.strikethrough {
-			html.push_str("text-decoration:line-through;");
+			rules.push(StyleRule::new("strike", "text-decoration:line-through;"));
 		}
 
 		// hidden
@@ -415,26 +897,63 @@ impl StyleNode {
 		// encircled
 		// overlined
 
-		if self.reverse {
-			let bg = self.background;
-			self.background = self.foreground;
-			self.foreground = bg;
+		let (foreground, background) = if self.reverse {
+			(self.background, self.foreground)
+		} else {
+			(self.foreground, self.background)
+		};
+
+		// Reversing default colors needs to know what the defaults are
+		let default_rule = |class: &str, property: &str, variable: &str, color: Option<Rgb>| match variables {
+			Some(prefix) => {
+				Some(StyleRule::new(&format!("{class}-reverse"), &format!("{property}:var(--{prefix}{variable});")))
+			},
+			None => color.map(|Rgb { r, g, b }| Self::color_rule(class, property, &Color::Rgb { r, g, b }, palette, None)),
+		};
+
+		if let Some(color) = foreground {
+			rules.push(Self::color_rule("fg", "color", &color, palette, variables));
+		} else if self.reverse {
+			rules.extend(default_rule("fg", "color", "bg", palette.background));
 		}
 
-		if let Some(color) = self.foreground {
-			html.push_str("color:");
-			Self::append_color(&mut html, &color);
-			html.push(';');
+		if let Some(color) = background {
+			rules.push(Self::color_rule("bg", "background", &color, palette, variables));
+		} else if self.reverse {
+			rules.extend(default_rule("bg", "background", "fg", palette.foreground));
 		}
 
-		if let Some(color) = self.background {
-			html.push_str("background:");
-			Self::append_color(&mut html, &color);
-			html.push(';');
+		rules
+	}
+
+	// Indexed colors are named by their palette index and true colors by their hex value, e.g. fg-1 or bg-rgb-ff0080
+	fn color_rule(class: &str, property: &str, color: &Color, palette: &Palette, variables: Option<&str>) -> StyleRule {
+		let index = color.palette_index();
+		let class = match color {
+			Color::Standard(color) => format!("{class}-{}", *color as u8),
+			Color::Bright(color) => format!("{class}-{}", 8 + *color as u8),
+			Color::Palette(index) => format!("{class}-{index}"),
+			Color::Rgb { r, g, b } => {
+				let mut class = format!("{class}-rgb-");
+				Self::push_hex(&mut class, *r);
+				Self::push_hex(&mut class, *g);
+				Self::push_hex(&mut class, *b);
+				class
+			},
+		};
+
+		let mut css = format!("{property}:");
+		match (variables, index.and_then(Palette::ansi_name)) {
+			(Some(prefix), Some(name)) => css.push_str(&format!("var(--{prefix}{name})")),
+			_ => Self::append_color(&mut css, color, palette),
 		}
+		css.push(';');
 
-		html.push_str("\">");
-		html
+		StyleRule { class, css }
+	}
+
+	pub fn to_html_close(&self) -> String {
+		format!("</{}>", self.tag())
 	}
 
 	#[inline]
@@ -728,6 +1247,32 @@ mod test {
 			}
 		);
 
+		// A color set after bold brightened the previous one stays as it was given when bold is removed
+		// like in \x1B[1;31mX\x1B[91mY\x1B[22mZ
+		let mut style = StyleNode::default();
+		for params in [vec![vec![1], vec![31]], vec![vec![91]], vec![vec![22]]] {
+			style.apply_sgr(&params);
+		}
+		assert_eq!(
+			style,
+			StyleNode {
+				foreground: Some(Color::Bright(EightBitColor::Red)),
+				..StyleNode::default()
+			}
+		);
+		for (params, background) in [
+			(vec![101], Some(Color::Bright(EightBitColor::Red))),
+			(vec![48, 5, 1], Some(Color::Palette(1))),
+			(vec![49], None),
+		] {
+			let style = StyleNode::from_ansi_node(&[vec![1], vec![41], params, vec![22]]);
+			assert_eq!((style.background, style.bg_bright_from_bold), (background, false));
+		}
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![1], vec![31], vec![38, 5, 9], vec![22]]).foreground,
+			Some(Color::Palette(9))
+		);
+
 		// Explicit bright should stay bright even after removing bold
 		assert_eq!(
 			StyleNode::from_ansi_node(&[vec![91]]),
@@ -813,6 +1358,83 @@ mod test {
 		);
 	}
 
+	#[test]
+	fn apply_sgr_accumulates_test() {
+		let mut style = StyleNode::default();
+		style.apply_sgr(&[vec![1]]);
+		style.apply_sgr(&[vec![31]]);
+		assert_eq!(
+			style,
+			StyleNode {
+				bold: true,
+				foreground: Some(Color::Bright(EightBitColor::Red)),
+				fg_bright_from_bold: true,
+				..StyleNode::default()
+			}
+		);
+
+		style.apply_sgr(&[vec![4]]);
+		style.apply_sgr(&[vec![22]]);
+		assert_eq!(
+			style,
+			StyleNode {
+				underline: Some(UnderlineStyle::Single),
+				foreground: Some(Color::Standard(EightBitColor::Red)),
+				..StyleNode::default()
+			}
+		);
+
+		style.apply_sgr(&[vec![0]]);
+		assert_eq!(style, StyleNode::default());
+	}
+
+	#[test]
+	fn effective_style_test() {
+		let mut style = StyleNode::default();
+		style.apply_sgr(&[vec![31], vec![1], vec![22]]);
+		style.apply_sgr(&[vec![39]]);
+		assert!(style.is_default());
+
+		let mut style = StyleNode::from_ansi_node(&[vec![91]]);
+		style.apply_sgr(&[vec![1]]);
+		style.apply_sgr(&[vec![22]]);
+		assert_eq!(style.effective(), StyleNode::from_ansi_node(&[vec![91]]));
+	}
+
+	#[test]
+	fn extended_color_forms_test() {
+		// Semicolon separated 256 colors
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![38], vec![5], vec![196], vec![1]]),
+			StyleNode {
+				bold: true,
+				foreground: Some(Color::Palette(196)),
+				..StyleNode::default()
+			}
+		);
+
+		// Semicolon separated RGB
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![48], vec![2], vec![255], vec![50], vec![0]]),
+			StyleNode {
+				background: Some(Color::Rgb { r: 255, g: 50, b: 0 }),
+				..StyleNode::default()
+			}
+		);
+
+		// Colon separated RGB with color space id
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![38, 2, 0, 255, 50, 0]]),
+			StyleNode {
+				foreground: Some(Color::Rgb { r: 255, g: 50, b: 0 }),
+				..StyleNode::default()
+			}
+		);
+
+		// Truncated sequences are ignored
+		assert_eq!(StyleNode::from_ansi_node(&[vec![38], vec![2], vec![255]]), StyleNode::default());
+	}
+
 	#[test]
 	fn underline_color_test() {
 		// 256 color underline
@@ -871,6 +1493,223 @@ mod test {
 		assert_eq!(StyleNode::from_ansi_node(&[vec![73], vec![75]]), StyleNode::default());
 	}
 
+	#[test]
+	fn to_sgr_test() {
+		assert_eq!(StyleNode::default().to_sgr(), "\x1B[0m");
+
+		let mut style = StyleNode::default();
+		style.apply_sgr(&[
+			vec![1],
+			vec![4, 3],
+			vec![31],
+			vec![48],
+			vec![5],
+			vec![208],
+			vec![58],
+			vec![2],
+			vec![1],
+			vec![2],
+			vec![3],
+		]);
+		assert_eq!(style.to_sgr(), "\x1B[0;1;4:3;31;48;5;208;58;2;1;2;3m");
+
+		let mut style = StyleNode::default();
+		style.apply_sgr(&[vec![3], vec![9], vec![95], vec![100], vec![12], vec![74]]);
+		assert_eq!(style.to_sgr(), "\x1B[0;3;9;74;12;95;100m");
+
+		// Writing a style out and reading it back gives the same style
+		for params in [
+			vec![vec![1], vec![32], vec![22]],
+			vec![vec![2], vec![7], vec![8], vec![53], vec![38, 2, 10, 20, 30]],
+			vec![vec![58, 5, 9], vec![4, 2], vec![6], vec![51]],
+		] {
+			let style = StyleNode::from_ansi_node(&params);
+			let sgr = style.to_sgr();
+			let groups = sgr[2..sgr.len() - 1]
+				.split(';')
+				.map(|group| group.split(':').map(|param| param.parse().unwrap()).collect())
+				.collect::<Vec<_>>();
+			assert_eq!(StyleNode::from_ansi_node(&groups), style);
+		}
+	}
+
+	#[test]
+	fn transition_to_test() {
+		let style = |params: &[Vec<u16>]| StyleNode::from_ansi_node(params);
+		let transition = |from: &[Vec<u16>], to: &[Vec<u16>]| style(from).transition_to(&style(to));
+
+		assert_eq!(transition(&[vec![31]], &[vec![31]]), "");
+		assert_eq!(transition(&[vec![1], vec![31]], &[vec![31], vec![1]]), "");
+		assert_eq!(transition(&[], &[vec![1], vec![4, 3], vec![31]]), "\x1B[1;4:3;31m");
+		assert_eq!(transition(&[vec![1], vec![2], vec![3], vec![31]], &[vec![2], vec![3], vec![31]]), "\x1B[22;2m");
+		assert_eq!(transition(&[vec![3], vec![5], vec![6], vec![44]], &[vec![3], vec![6], vec![44]]), "\x1B[25;6m");
+		assert_eq!(
+			transition(
+				&[vec![1], vec![3], vec![4], vec![73], vec![12], vec![58, 5, 1]],
+				&[vec![1], vec![3], vec![4], vec![74]]
+			),
+			"\x1B[10;74;59m"
+		);
+		assert_eq!(transition(&[vec![73], vec![12], vec![58, 5, 1]], &[vec![74]]), "\x1B[0;74m");
+		// Fraktur can only be turned off by starting over
+		assert_eq!(transition(&[vec![20], vec![3]], &[vec![3]]), "\x1B[0;3m");
+
+		// The sequence turns the first style into the second one
+		let styles = [
+			vec![],
+			vec![vec![1], vec![32]],
+			vec![vec![32], vec![1], vec![22]],
+			vec![vec![2], vec![41], vec![1]],
+			vec![vec![5], vec![7], vec![8], vec![9], vec![26], vec![51], vec![53]],
+			vec![vec![52], vec![6], vec![4, 5], vec![58, 2, 1, 2, 3], vec![38, 5, 208]],
+			vec![vec![20], vec![15], vec![73], vec![48, 2, 10, 20, 30], vec![97]],
+		];
+		for from in &styles {
+			for to in &styles {
+				let mut current = style(from);
+				let sgr = current.transition_to(&style(to));
+				let mut replayed = style(from);
+				if !sgr.is_empty() {
+					replayed.apply_sgr(&StyleNode::sgr_groups(&sgr[2..sgr.len() - 1]));
+				}
+				assert_eq!(replayed.effective(), style(to).effective(), "{from:?} to {to:?} with {sgr:?}");
+				assert_eq!(replayed, current);
+			}
+		}
+	}
+
+	#[test]
+	fn to_html_for_email_test() {
+		let palette = crate::themes::Theme::XTERM.palette();
+		let email = |params: &[Vec<u16>]| {
+			let style = StyleNode::from_ansi_node(params);
+			(style.to_html_for_email(&palette), style.to_html_close_for_email(&palette))
+		};
+
+		assert_eq!(
+			email(&[vec![1], vec![4, 3], vec![9], vec![31], vec![5]]),
+			(
+				String::from(
+					"<font color=\"#ff0000\" style=\"font-weight:bold;text-decoration:underline line-through;color:#ff0000;\">"
+				),
+				String::from("</font>")
+			)
+		);
+		// Dim is blended with the background and reverse uses the default colors
+		assert_eq!(
+			email(&[vec![2], vec![7]]),
+			(
+				String::from("<font color=\"#808080\" style=\"color:#808080;background-color:#000000;\">"),
+				String::from("</font>")
+			)
+		);
+		assert_eq!(
+			email(&[vec![3], vec![44], vec![74]]),
+			(String::from("<sub style=\"font-style:italic;background-color:#0000ee;\">"), String::from("</sub>"))
+		);
+		assert_eq!(email(&[vec![5], vec![8], vec![53]]), (String::new(), String::new()));
+		assert_eq!(email(&[vec![74]]), (String::from("<sub>"), String::from("</sub>")));
+	}
+
+	#[test]
+	fn to_html_with_palette_test() {
+		let mut palette = Palette::default();
+		palette.set(1, Rgb::new(0x12, 0x34, 0x56));
+
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![31]]).to_html_with_palette(&palette),
+			String::from("<span style=\"color:#123456;\">")
+		);
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![38], vec![5], vec![1]]).to_html_with_palette(&palette),
+			String::from("<span style=\"color:#123456;\">")
+		);
+
+		// Reverse video without colors uses the default colors if there are any
+		assert_eq!(StyleNode::from_ansi_node(&[vec![7]]).to_html_with_palette(&palette), String::from("<span style=\"\">"));
+		palette.foreground = Some(Rgb::new(0xee, 0xee, 0xee));
+		palette.background = Some(Rgb::new(0x11, 0x11, 0x11));
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![7]]).to_html_with_palette(&palette),
+			String::from("<span style=\"color:#111;background:#eee;\">")
+		);
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![7], vec![31]]).to_html_with_palette(&palette),
+			String::from("<span style=\"color:#111;background:#123456;\">")
+		);
+	}
+
+	#[test]
+	fn resolved_colors_test() {
+		let mut palette = Palette::default();
+		assert_eq!(StyleNode::from_ansi_node(&[vec![31]]).resolved_colors(&palette), (Some(Rgb::new(0xcd, 0, 0)), None));
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![7], vec![31]]).resolved_colors(&palette),
+			(None, Some(Rgb::new(0xcd, 0, 0)))
+		);
+
+		palette.background = Some(Rgb::new(0x11, 0x11, 0x11));
+		assert_eq!(
+			StyleNode::from_ansi_node(&[vec![7], vec![31]]).resolved_colors(&palette),
+			(Some(Rgb::new(0x11, 0x11, 0x11)), Some(Rgb::new(0xcd, 0, 0)))
+		);
+	}
+
+	#[test]
+	fn to_html_with_classes_test() {
+		let palette = Palette::default();
+		let classes = |params: &[Vec<u16>], prefix: &str| {
+			let style = StyleNode::from_ansi_node(params);
+			style.to_html_with_classes(&style.rules(&palette, None), prefix)
+		};
+		assert_eq!(
+			classes(
+				&[
+					vec![1],
+					vec![4, 3],
+					vec![38, 5, 208],
+					vec![48, 2, 0, 0, 255],
+					vec![58, 5, 2]
+				],
+				"sv-"
+			),
+			String::from("<span class=\"sv-bold sv-underline-curly sv-ul-2 sv-fg-208 sv-bg-rgb-0000ff\">")
+		);
+		assert_eq!(classes(&[vec![7], vec![94]], "x-"), String::from("<span class=\"x-bg-12\">"));
+		assert_eq!(classes(&[vec![73]], "sv-"), String::from("<sup>"));
+	}
+
+	#[test]
+	fn css_variables_test() {
+		let mut palette = Palette::default();
+		let rules = |params: &[Vec<u16>], palette: &Palette| {
+			let style = StyleNode::from_ansi_node(params);
+			style.to_html_with_rules(&style.rules(palette, Some("sv-")))
+		};
+
+		assert_eq!(
+			rules(&[vec![31], vec![104], vec![58, 5, 3]], &palette),
+			String::from(
+				"<span style=\"text-decoration-color:var(--sv-yellow);color:var(--sv-red);background:var(--sv-bright-blue);\">"
+			)
+		);
+		// Only the 16 ANSI colors are themed
+		assert_eq!(
+			rules(&[vec![38, 5, 208], vec![48, 2, 1, 2, 3]], &palette),
+			String::from("<span style=\"color:#f60;background:#010203;\">")
+		);
+		// Reversed default colors swap the variables even when the palette doesn't know them
+		assert_eq!(
+			rules(&[vec![7]], &palette),
+			String::from("<span style=\"color:var(--sv-bg);background:var(--sv-fg);\">")
+		);
+		palette.foreground = Some(Rgb::new(0xee, 0xee, 0xee));
+		assert_eq!(
+			rules(&[vec![7], vec![32]], &palette),
+			String::from("<span style=\"color:var(--sv-bg);background:var(--sv-green);\">")
+		);
+	}
+
 	#[test]
 	fn to_html_test() {
 		// Standard colors
@@ -945,4 +1784,29 @@ mod test {
 			String::from("<span style=\"color:#123;\">")
 		);
 	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn serde_shape_test() {
+		let style = StyleNode::from_ansi_node(&[vec![1], vec![31], vec![4, 3], vec![12], vec![58, 5, 4], vec![9]]);
+		let json = serde_json::from_str::<serde_json::Value>(&style.to_json(&Palette::default()).to_string()).unwrap();
+		let serde = serde_json::to_value(style).unwrap();
+
+		let (json, serde) = (json.as_object().unwrap(), serde.as_object().unwrap());
+		assert_eq!(json.keys().collect::<Vec<_>>(), serde.keys().collect::<Vec<_>>());
+		// Only colors differ since they are resolved
+		for (key, value) in json {
+			if !["foreground", "background", "underline_color"].contains(&key.as_str()) {
+				assert_eq!(value, &serde[key], "{key}");
+			}
+		}
+		assert_eq!(serde["foreground"], serde_json::json!({"bright": "red"}));
+		assert_eq!(serde["underline_color"], serde_json::json!({"palette": 4}));
+
+		// Whether bold made the color bright is bookkeeping for SGR 22 and isn't kept
+		assert_eq!(
+			serde_json::from_value::<StyleNode>(serde_json::Value::Object(serde.clone())).unwrap().effective(),
+			style.effective()
+		);
+	}
 }
@@ -0,0 +1,430 @@
+use crate::{
+	asciicast::Animation,
+	html::push_escaped,
+	palette::{Palette, Rgb},
+	screen::{Cell, Screen, TitleSelection},
+	styles::StyleNode,
+	themes::Theme,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+	pub font_family: String,
+	pub font_size: f32,
+	/// Line height as a multiple of the font size
+	pub line_height: f32,
+	/// Column width as a multiple of the font size, about 0.6 for most monospace fonts
+	pub column_width: f32,
+	pub padding: f32,
+	/// Draw a window frame with a title bar around the output
+	pub window_chrome: bool,
+	/// Which terminal title to show in the window chrome
+	pub title: TitleSelection,
+}
+
+impl Default for SvgOptions {
+	fn default() -> Self {
+		Self {
+			font_family: String::from(Self::DEFAULT_FONT_FAMILY),
+			font_size: 14.0,
+			line_height: 1.2,
+			column_width: 0.6,
+			padding: 16.0,
+			window_chrome: false,
+			title: TitleSelection::default(),
+		}
+	}
+}
+
+impl SvgOptions {
+	pub const DEFAULT_FONT_FAMILY: &str = "ui-monospace,SFMono-Regular,Menlo,Consolas,'Liberation Mono',monospace";
+	const CHROME_HEIGHT: f32 = 32.0;
+	const CHROME_BUTTONS: [&str; 3] = ["#ff5f56", "#ffbd2e", "#27c93f"];
+}
+
+pub struct SvgRenderer<'a> {
+	svg: String,
+	options: &'a SvgOptions,
+	palette: Palette,
+	top: f32,
+}
+
+impl<'a> SvgRenderer<'a> {
+	pub fn render_screen(screen: &'a Screen, options: &'a SvgOptions) -> String {
+		let mut lines = screen.lines().collect::<Vec<_>>();
+		// The cursor line after a final newline would only add space below the output
+		while lines.last().is_some_and(|line| line.is_empty()) {
+			lines.pop();
+		}
+		let columns = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+
+		let mut renderer = Self::new(screen, options);
+		renderer.push_header(columns, lines.len(), screen.title(options.title));
+		for (row, line) in lines.iter().enumerate() {
+			renderer.push_cells(row, line);
+		}
+
+		renderer.svg.push_str("</svg>");
+		renderer.svg
+	}
+
+	/// Renders all frames stacked in a strip that a CSS animation moves through one frame at a time
+	pub fn render_animation(animation: &'a Animation, options: &'a SvgOptions) -> String {
+		let Some(last) = animation.frames.last() else {
+			return String::new();
+		};
+
+		let mut renderer = Self::new(&last.screen, options);
+		let width = renderer.push_header(
+			animation.width,
+			animation.height,
+			animation.title.as_deref().or_else(|| last.screen.title(options.title)),
+		);
+		let frame_height = animation.height as f32 * options.line_height * options.font_size;
+
+		let mut keyframes = String::new();
+		for (index, frame) in animation.frames.iter().enumerate() {
+			keyframes.push_str(&format!(
+				"{}%{{transform:translateY({}px)}}",
+				percent(frame.time / animation.duration),
+				number(0.0 - index as f32 * frame_height)
+			));
+		}
+		keyframes.push_str(&format!(
+			"100%{{transform:translateY({}px)}}",
+			number(0.0 - (animation.frames.len() - 1) as f32 * frame_height)
+		));
+
+		let header = renderer.svg.len();
+		let strip_top = renderer.top;
+		for (index, frame) in animation.frames.iter().enumerate() {
+			renderer.top = index as f32 * frame_height;
+			for (row, line) in frame.screen.viewport().iter().enumerate() {
+				renderer.push_cells(row, line);
+			}
+		}
+
+		// Styles in an inline SVG apply to the whole page so the names are made unique to this animation
+		let content = format!("{}{keyframes}{}", animation.duration, &renderer.svg[header..]);
+		let id = format!("sv-{:08x}", fnv1a(content.as_bytes()));
+		// The nested svg clips the strip to a single frame
+		let strip = format!(
+			"<style>@keyframes {id}-play{{{keyframes}}}.{id}-strip{{animation:{id}-play {}s step-end infinite}}</style>\n\
+			<svg y=\"{}\" width=\"{}\" height=\"{}\">\n<g class=\"{id}-strip\">\n",
+			number(animation.duration as f32),
+			number(strip_top),
+			number(width),
+			number(frame_height)
+		);
+		renderer.svg.insert_str(header, &strip);
+		renderer.svg.push_str("</g>\n</svg>\n</svg>");
+		renderer.svg
+	}
+
+	fn new(screen: &Screen, options: &'a SvgOptions) -> Self {
+		// An image has no page to inherit colors from so unknown default colors fall back to xterm's
+		let mut palette = screen.palette().clone();
+		palette.foreground = palette.foreground.or(Some(Theme::XTERM.foreground));
+		palette.background = palette.background.or(Some(Theme::XTERM.background));
+
+		Self {
+			svg: String::new(),
+			options,
+			palette,
+			top: 0.0,
+		}
+	}
+
+	// Opens the svg sized for the given columns and rows, draws the background and chrome and returns the width
+	fn push_header(&mut self, columns: usize, rows: usize, title: Option<&str>) -> f32 {
+		let options = self.options;
+		let chrome_height = if options.window_chrome {
+			SvgOptions::CHROME_HEIGHT
+		} else {
+			0.0
+		};
+		let width = options.padding * 2.0 + columns as f32 * options.column_width * options.font_size;
+		let height = chrome_height + options.padding * 2.0 + rows as f32 * options.line_height * options.font_size;
+		self.top = chrome_height + options.padding;
+
+		self.svg.push_str(&format!(
+			"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"",
+			number(width),
+			number(height)
+		));
+		push_escaped(&mut self.svg, &options.font_family);
+		self.svg.push_str(&format!("\" font-size=\"{}\" xml:space=\"preserve\">\n", number(options.font_size)));
+
+		self.svg.push_str("<rect width=\"100%\" height=\"100%\"");
+		if options.window_chrome {
+			self.svg.push_str(" rx=\"8\"");
+		}
+		self.push_attribute_color("fill", self.palette.background);
+		self.svg.push_str("/>\n");
+
+		if options.window_chrome {
+			self.push_chrome(title, width);
+		}
+
+		width
+	}
+
+	fn push_cells(&mut self, row: usize, line: &[Option<Cell>]) {
+		self.push_line(row, line.iter().map(|cell| cell.map_or((' ', StyleNode::default()), |cell| (cell.ch, cell.style))));
+	}
+
+	fn push_chrome(&mut self, title: Option<&str>, width: f32) {
+		let center = SvgOptions::CHROME_HEIGHT / 2.0;
+		for (index, color) in SvgOptions::CHROME_BUTTONS.iter().enumerate() {
+			self.svg.push_str(&format!(
+				"<circle cx=\"{}\" cy=\"{}\" r=\"6\" fill=\"{color}\"/>\n",
+				number(self.options.padding + 6.0 + index as f32 * 20.0),
+				number(center)
+			));
+		}
+
+		if let Some(title) = title {
+			self.svg.push_str(&format!(
+				"<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\" opacity=\".6\"",
+				number(width / 2.0),
+				number(center)
+			));
+			self.push_attribute_color("fill", self.palette.foreground);
+			self.svg.push('>');
+			push_escaped(&mut self.svg, title);
+			self.svg.push_str("</text>\n");
+		}
+	}
+
+	// Cells with the same style are grouped into runs that each get their own rect, tspan and lines
+	fn push_line(&mut self, row: usize, cells: impl Iterator<Item = (char, StyleNode)>) {
+		let mut runs: Vec<(usize, StyleNode, String)> = Vec::new();
+		for (column, (c, style)) in cells.enumerate() {
+			let style = style.effective();
+			match runs.last_mut() {
+				Some((_, run_style, text)) if *run_style == style => text.push(c),
+				_ => runs.push((column, style, c.to_string())),
+			}
+		}
+
+		let font_size = self.options.font_size;
+		let line_height = self.options.line_height * font_size;
+		let column_width = self.options.column_width * font_size;
+		let top = self.top + row as f32 * line_height;
+		// Center the glyphs in the line, the baseline sits about 0.35em below the middle
+		let baseline = top + line_height / 2.0 + font_size * 0.35;
+
+		for (column, style, text) in &runs {
+			let (_, background) = style.resolved_colors(&self.palette);
+			if let Some(background) = background.filter(|color| Some(*color) != self.palette.background) {
+				self.svg.push_str(&format!(
+					"<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+					number(self.options.padding + *column as f32 * column_width),
+					number(top),
+					number(text.chars().count() as f32 * column_width),
+					number(line_height)
+				));
+				self.push_attribute_color("fill", Some(background));
+				self.svg.push_str("/>\n");
+			}
+		}
+
+		let visible = runs.iter().filter(|(_, style, text)| !style.hidden() && !text.trim().is_empty()).collect::<Vec<_>>();
+		if !visible.is_empty() {
+			self.svg.push_str(&format!("<text y=\"{}\"", number(baseline)));
+			self.push_attribute_color("fill", self.palette.foreground);
+			self.svg.push('>');
+
+			for (column, style, text) in visible {
+				// Leading spaces are skipped so the x of the run can be exact
+				let trimmed = text.trim_start_matches(' ');
+				let column = column + (text.len() - trimmed.len());
+				let (foreground, _) = style.resolved_colors(&self.palette);
+
+				self.svg.push_str(&format!("<tspan x=\"{}\"", number(self.options.padding + column as f32 * column_width)));
+				if foreground != self.palette.foreground {
+					self.push_attribute_color("fill", foreground);
+				}
+				if style.bold() {
+					self.svg.push_str(" font-weight=\"bold\"");
+				}
+				if style.italic() {
+					self.svg.push_str(" font-style=\"italic\"");
+				}
+				if style.dim() {
+					self.svg.push_str(" fill-opacity=\".5\"");
+				}
+				self.svg.push('>');
+				push_escaped(&mut self.svg, trimmed.trim_end_matches(' '));
+				self.svg.push_str("</tspan>");
+			}
+
+			self.svg.push_str("</text>\n");
+		}
+
+		for (column, style, text) in &runs {
+			let (foreground, _) = style.resolved_colors(&self.palette);
+			let color = foreground.or(self.palette.foreground);
+			let x1 = self.options.padding + *column as f32 * column_width;
+			let x2 = x1 + text.chars().count() as f32 * column_width;
+
+			if style.underline().is_some() {
+				let underline_color = style.underline_color().map(|color| self.palette.resolve(&color)).or(color);
+				self.push_line_decoration(x1, x2, baseline + font_size * 0.15, underline_color);
+			}
+			if style.strikethrough() {
+				self.push_line_decoration(x1, x2, baseline - font_size * 0.3, color);
+			}
+		}
+	}
+
+	fn push_line_decoration(&mut self, x1: f32, x2: f32, y: f32, color: Option<Rgb>) {
+		self.svg.push_str(&format!(
+			"<line x1=\"{}\" y1=\"{2}\" x2=\"{}\" y2=\"{2}\" stroke-width=\"1\"",
+			number(x1),
+			number(x2),
+			number(y)
+		));
+		self.push_attribute_color("stroke", color);
+		self.svg.push_str("/>\n");
+	}
+
+	fn push_attribute_color(&mut self, name: &str, color: Option<Rgb>) {
+		if let Some(color) = color {
+			self.svg.push_str(&format!(" {name}=\""));
+			StyleNode::append_rgb(&mut self.svg, color);
+			self.svg.push('"');
+		}
+	}
+}
+
+// Two decimals are plenty for pixel coordinates and trailing zeros are dropped
+fn number(value: f32) -> String {
+	let number = format!("{value:.2}");
+	number.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// Keyframe offsets need more precision than coordinates so frames of long recordings don't collide
+fn percent(fraction: f64) -> String {
+	let percent = format!("{:.3}", fraction * 100.0);
+	percent.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// FNV-1a, a stable hash so the same animation always gets the same id
+fn fnv1a(bytes: &[u8]) -> u32 {
+	bytes.iter().fold(0x811c_9dc5, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x0100_0193))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8], options: &SvgOptions) -> String {
+		SvgRenderer::render_screen(&Screen::from_nodes(&TerminalOutputParser::parse_to_nodes(input)), options)
+	}
+
+	fn options() -> SvgOptions {
+		SvgOptions {
+			font_family: String::from("monospace"),
+			font_size: 10.0,
+			line_height: 2.0,
+			column_width: 1.0,
+			padding: 5.0,
+			..SvgOptions::default()
+		}
+	}
+
+	#[test]
+	fn number_test() {
+		assert_eq!(number(10.0), "10");
+		assert_eq!(number(8.4), "8.4");
+		assert_eq!(number(1.0 / 3.0), "0.33");
+		assert_eq!(number(0.0), "0");
+	}
+
+	#[test]
+	fn plain_text_test() {
+		assert_eq!(
+			render(b"hi\n  <b>\n", &options()),
+			String::from(
+				"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"60\" height=\"50\" viewBox=\"0 0 60 50\" font-family=\"monospace\" font-size=\"10\" xml:space=\"preserve\">\n<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n<text y=\"18.5\" fill=\"#000\"><tspan x=\"5\">hi</tspan></text>\n<text y=\"38.5\" fill=\"#000\"><tspan x=\"25\">&lt;b&gt;</tspan></text>\n</svg>"
+			)
+		);
+		assert_eq!(
+			render(b"", &options()),
+			String::from(
+				"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"10\" height=\"10\" viewBox=\"0 0 10 10\" font-family=\"monospace\" font-size=\"10\" xml:space=\"preserve\">\n<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n</svg>"
+			)
+		);
+	}
+
+	#[test]
+	fn styled_text_test() {
+		let svg = render(b"\x1B[1;31mA\x1B[0m \x1B[3;2;44mB\x1B[0m\x1B[8mC", &options());
+		assert!(svg.contains(
+			"<rect x=\"25\" y=\"5\" width=\"10\" height=\"20\" fill=\"#00e\"/>\n<text y=\"18.5\" fill=\"#000\"><tspan x=\"5\" fill=\"#f00\" font-weight=\"bold\">A</tspan><tspan x=\"25\" font-style=\"italic\" fill-opacity=\".5\">B</tspan></text>\n"
+		));
+	}
+
+	#[test]
+	fn reverse_test() {
+		let svg = render(b"\x1B]10;#eee\x07\x1B]11;#111\x07\x1B[7mA", &options());
+		assert!(svg.contains("<rect width=\"100%\" height=\"100%\" fill=\"#111\"/>"));
+		assert!(svg.contains("<rect x=\"5\" y=\"5\" width=\"10\" height=\"20\" fill=\"#eee\"/>"));
+		assert!(svg.contains("<text y=\"18.5\" fill=\"#eee\"><tspan x=\"5\" fill=\"#111\">A</tspan></text>"));
+	}
+
+	#[test]
+	fn decoration_test() {
+		let svg = render(b"\x1B[4;58;5;1mAB\x1B[24;9mC", &options());
+		assert!(svg.contains("<line x1=\"5\" y1=\"20\" x2=\"25\" y2=\"20\" stroke-width=\"1\" stroke=\"#cd0000\"/>\n"));
+		assert!(svg.contains("<line x1=\"25\" y1=\"15.5\" x2=\"35\" y2=\"15.5\" stroke-width=\"1\" stroke=\"#000\"/>\n"));
+	}
+
+	#[test]
+	fn window_chrome_test() {
+		let svg = render(
+			b"\x1B]2;cargo & test\x07A",
+			&SvgOptions {
+				window_chrome: true,
+				..options()
+			},
+		);
+		assert!(
+			svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"62\" viewBox=\"0 0 20 62\"")
+		);
+		assert!(svg.contains("<rect width=\"100%\" height=\"100%\" rx=\"8\" fill=\"#fff\"/>\n<circle cx=\"11\" cy=\"16\" r=\"6\" fill=\"#ff5f56\"/>\n"));
+		assert!(svg.contains("<text x=\"10\" y=\"16\" text-anchor=\"middle\" dominant-baseline=\"middle\" opacity=\".6\" fill=\"#000\">cargo &amp; test</text>\n"));
+		assert!(svg.contains("<text y=\"50.5\" fill=\"#000\"><tspan x=\"5\">A</tspan></text>"));
+	}
+
+	#[test]
+	fn animation_test() {
+		use crate::asciicast::Asciicast;
+
+		let cast = Asciicast {
+			width: 3,
+			height: 1,
+			title: None,
+			palette: None,
+			events: vec![(1.0, String::from("a")), (3.0, String::from("\x1B[31mb"))],
+		};
+		let options = options();
+
+		assert_eq!(
+			SvgRenderer::render_animation(&cast.animation(Palette::default()), &options),
+			String::from(
+				"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"30\" viewBox=\"0 0 40 30\" font-family=\"monospace\" font-size=\"10\" xml:space=\"preserve\">\n<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n<style>@keyframes sv-3b3b521e-play{0%{transform:translateY(0px)}20%{transform:translateY(-20px)}60%{transform:translateY(-40px)}100%{transform:translateY(-40px)}}.sv-3b3b521e-strip{animation:sv-3b3b521e-play 5s step-end infinite}</style>\n<svg y=\"5\" width=\"40\" height=\"20\">\n<g class=\"sv-3b3b521e-strip\">\n<text y=\"33.5\" fill=\"#000\"><tspan x=\"5\">a</tspan></text>\n<text y=\"53.5\" fill=\"#000\"><tspan x=\"5\">a</tspan><tspan x=\"15\" fill=\"#cd0000\">b</tspan></text>\n</g>\n</svg>\n</svg>"
+			)
+		);
+
+		// Another recording gets its own names so both can be inlined in the same page
+		let other = Asciicast {
+			events: vec![(1.0, String::from("c"))],
+			..cast
+		};
+		let svg = SvgRenderer::render_animation(&other.animation(Palette::default()), &options);
+		assert!(!svg.contains("sv-3b3b521e") && svg.contains("<g class=\"sv-"));
+	}
+}
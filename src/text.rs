@@ -0,0 +1,116 @@
+use crate::screen::{Cell, Screen};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextOptions {
+	/// Print the url of a hyperlink after its text like `text (url)`
+	pub links: bool,
+}
+
+/// Plain UTF-8 text of a screen without any styles, as a terminal would display it
+pub struct TextRenderer;
+
+impl TextRenderer {
+	pub fn render_screen(screen: &Screen, options: &TextOptions) -> String {
+		let mut text = String::new();
+
+		for (row, line) in screen.lines().enumerate() {
+			if row > 0 {
+				text.push('\n');
+			}
+			Self::push_line(&mut text, screen, line, options);
+		}
+
+		text
+	}
+
+	fn push_line(text: &mut String, screen: &Screen, line: &[Option<Cell>], options: &TextOptions) {
+		let mut open_link = None;
+		let mut link_text = String::new();
+
+		for cell in line {
+			// Hidden text takes up its space on the screen but can't be seen
+			let (ch, link) = cell.map_or((' ', None), |cell| (if cell.style.hidden() { ' ' } else { cell.ch }, cell.link));
+
+			if options.links && link != open_link {
+				Self::push_url(text, screen, open_link, &link_text);
+				open_link = link;
+				link_text.clear();
+			}
+			text.push(ch);
+			link_text.push(ch);
+		}
+
+		if options.links {
+			Self::push_url(text, screen, open_link, &link_text);
+		}
+	}
+
+	// Links that already show their url as text don't need it twice
+	fn push_url(text: &mut String, screen: &Screen, link: Option<usize>, link_text: &str) {
+		if let Some(link) = link.and_then(|link| screen.links().get(link))
+			&& link_text.trim() != link.url
+		{
+			text.push_str(&format!(" ({})", link.url));
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::nodes::TerminalOutputParser;
+
+	fn render(input: &[u8], options: &TextOptions) -> String {
+		let mut screen = Screen::default();
+		screen.apply_all(&TerminalOutputParser::parse_to_nodes(input));
+		TextRenderer::render_screen(&screen, options)
+	}
+
+	#[test]
+	fn strip_test() {
+		let options = TextOptions::default();
+
+		assert_eq!(render(b"\x1B[1;31mError:\x1B[0m file not found\n", &options), "Error: file not found\n");
+		assert_eq!(render(b"\x1B]0;title\x07\x1B[?25lhidden cursor\x1B[?25h", &options), "hidden cursor");
+		assert_eq!(render(b"\x1B(Bcharset\x1B=", &options), "charset");
+		assert_eq!(render("\x1B[38;2;1;2;3m\u{1F980} unicode\x1B[m".as_bytes(), &options), "\u{1F980} unicode");
+	}
+
+	#[test]
+	fn hidden_test() {
+		let options = TextOptions::default();
+
+		assert_eq!(render(b"password: \x1B[8msecret\x1B[28m ok", &options), "password:        ok");
+		assert_eq!(render(b"\x1B[8ma\x1B[0mb", &options), " b");
+	}
+
+	#[test]
+	fn carriage_return_and_backspace_test() {
+		let options = TextOptions::default();
+
+		assert_eq!(render(b"10%\r50%\r100%\n", &options), "100%\n");
+		assert_eq!(render(b"Downloading...\r\x1B[KDone\n", &options), "Done\n");
+		assert_eq!(render(b"abc\x08\x08X", &options), "aXc");
+		// Man pages make bold text by overstriking characters
+		assert_eq!(render(b"N\x08NA\x08AM\x08ME\x08E", &options), "NAME");
+		assert_eq!(render(b"line one\r\nline two\r\n", &options), "line one\nline two\n");
+		// Cursor movement leaves gaps as spaces
+		assert_eq!(render(b"a\x1B[3Cb", &options), "a   b");
+	}
+
+	#[test]
+	fn links_test() {
+		let input = b"see \x1B]8;;https://example.com\x1B\\the docs\x1B]8;;\x1B\\ or \x1B]8;;https://example.org\x1B\\https://example.org\x1B]8;;\x1B\\";
+
+		assert_eq!(render(input, &TextOptions::default()), "see the docs or https://example.org");
+		assert_eq!(
+			render(input, &TextOptions { links: true }),
+			"see the docs (https://example.com) or https://example.org"
+		);
+		// A link at the end of a line ends there
+		assert_eq!(
+			render(b"\x1B]8;;https://a.example\x07a\nb\x1B]8;;\x07", &TextOptions { links: true }),
+			"a (https://a.example)\nb (https://a.example)"
+		);
+	}
+}
@@ -0,0 +1,963 @@
+use std::path::Path;
+
+use crate::{
+	json::{Json, JsonError},
+	palette::{Palette, Rgb},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThemeFileError {
+	Io(String),
+	UnknownFormat(String),
+	Syntax { line: usize, message: String },
+	InvalidColor { name: String, value: String },
+	SchemeNotFound(String),
+	NoColors,
+}
+
+impl std::fmt::Display for ThemeFileError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			ThemeFileError::Io(error) => write!(f, "Could not read theme file: {error}"),
+			ThemeFileError::UnknownFormat(path) => write!(
+				f,
+				"Could not tell the theme format of \"{path}\", expected .itermcolors, .toml, .yml, .yaml, .json, .conf or an Xresources file"
+			),
+			ThemeFileError::Syntax { line, message } => write!(f, "Syntax error on line {line}: {message}"),
+			ThemeFileError::InvalidColor { name, value } => write!(f, "Invalid color \"{value}\" for \"{name}\""),
+			ThemeFileError::SchemeNotFound(name) => write!(f, "No color scheme named \"{name}\" found"),
+			ThemeFileError::NoColors => write!(f, "No colors found in theme file"),
+		}
+	}
+}
+
+impl std::error::Error for ThemeFileError {}
+
+impl From<JsonError> for ThemeFileError {
+	fn from(error: JsonError) -> Self {
+		ThemeFileError::Syntax {
+			line: error.line,
+			message: error.message,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThemeFormat {
+	/// iTerm2 .itermcolors property list
+	ITerm,
+	AlacrittyToml,
+	AlacrittyYaml,
+	/// A Windows Terminal settings.json or a single scheme object
+	WindowsTerminal,
+	Kitty,
+	Xresources,
+}
+
+impl ThemeFormat {
+	pub fn from_path(path: &Path) -> Option<Self> {
+		let file_name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+		let extension = path.extension().map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+		match extension.as_deref() {
+			Some("itermcolors") => Some(ThemeFormat::ITerm),
+			Some("toml") => Some(ThemeFormat::AlacrittyToml),
+			Some("yml" | "yaml") => Some(ThemeFormat::AlacrittyYaml),
+			Some("json") => Some(ThemeFormat::WindowsTerminal),
+			Some("conf") => Some(ThemeFormat::Kitty),
+			_ if file_name.contains("xresources") || file_name.contains("xdefaults") => Some(ThemeFormat::Xresources),
+			_ => None,
+		}
+	}
+
+	/// Loads a theme file and picks its format from the file name.
+	/// `scheme` selects a scheme by name in files that can hold more than one.
+	pub fn load(path: &Path, scheme: Option<&str>) -> Result<Palette, ThemeFileError> {
+		let format = Self::from_path(path).ok_or_else(|| ThemeFileError::UnknownFormat(path.display().to_string()))?;
+		let contents = std::fs::read_to_string(path).map_err(|error| ThemeFileError::Io(error.to_string()))?;
+		format.parse(&contents, scheme)
+	}
+
+	pub fn parse(&self, contents: &str, scheme: Option<&str>) -> Result<Palette, ThemeFileError> {
+		let mut colors = ThemeColors::default();
+
+		match self {
+			ThemeFormat::ITerm => Self::parse_iterm(contents, &mut colors)?,
+			ThemeFormat::AlacrittyToml => Self::parse_alacritty(&Self::parse_toml(contents)?, &mut colors)?,
+			ThemeFormat::AlacrittyYaml => Self::parse_alacritty(&Self::parse_yaml(contents)?, &mut colors)?,
+			ThemeFormat::WindowsTerminal => Self::parse_windows_terminal(contents, scheme, &mut colors)?,
+			ThemeFormat::Kitty => Self::parse_kitty(contents, &mut colors)?,
+			ThemeFormat::Xresources => Self::parse_xresources(contents, &mut colors)?,
+		}
+
+		colors.into_palette()
+	}
+
+	fn parse_color(name: &str, value: &str) -> Result<Rgb, ThemeFileError> {
+		let value = value.trim();
+		let spec = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+			Some(hex) => format!("#{hex}"),
+			None => value.to_string(),
+		};
+
+		Rgb::parse_x11(&spec).ok_or_else(|| ThemeFileError::InvalidColor {
+			name: name.to_string(),
+			value: value.to_string(),
+		})
+	}
+
+	// iTerm2 stores each color as a dict of float components between 0 and 1
+	fn parse_iterm(contents: &str, colors: &mut ThemeColors) -> Result<(), ThemeFileError> {
+		let plist = Plist::parse(contents)?;
+		let PlistValue::Dict(entries) = plist else {
+			return Err(ThemeFileError::Syntax {
+				line: 1,
+				message: String::from("expected a <dict> at the root of the property list"),
+			});
+		};
+
+		for (key, value) in &entries {
+			let PlistValue::Dict(components) = value else {
+				continue;
+			};
+			let component = |name: &str| -> Result<u8, ThemeFileError> {
+				match components.iter().find(|(key, _)| key == name) {
+					Some((_, PlistValue::Text(text))) => text
+						.trim()
+						.parse::<f64>()
+						.ok()
+						.filter(|value| (0.0..=1.0).contains(value))
+						.map(|value| (value * 255.0).round() as u8)
+						.ok_or_else(|| ThemeFileError::InvalidColor {
+							name: format!("{key} {name}"),
+							value: text.clone(),
+						}),
+					_ => Err(ThemeFileError::InvalidColor {
+						name: key.clone(),
+						value: format!("missing {name}"),
+					}),
+				}
+			};
+
+			let slot = if let Some(index) = key.strip_prefix("Ansi ").and_then(|rest| rest.strip_suffix(" Color")) {
+				match index.parse::<usize>() {
+					Ok(index) if index < 16 => &mut colors.ansi[index],
+					_ => continue,
+				}
+			} else if key == "Foreground Color" {
+				&mut colors.foreground
+			} else if key == "Background Color" {
+				&mut colors.background
+			} else {
+				continue;
+			};
+
+			*slot = Some(Rgb::new(component("Red Component")?, component("Green Component")?, component("Blue Component")?));
+		}
+
+		Ok(())
+	}
+
+	// Alacritty keeps colors under colors.primary, colors.normal and colors.bright in both its TOML and YAML configs
+	fn parse_alacritty(entries: &[(String, String)], colors: &mut ThemeColors) -> Result<(), ThemeFileError> {
+		for (key, value) in entries {
+			let Some(name) = key.strip_prefix("colors.") else {
+				continue;
+			};
+
+			let slot = match name.split_once('.') {
+				Some(("primary", "foreground")) => &mut colors.foreground,
+				Some(("primary", "background")) => &mut colors.background,
+				Some((group @ ("normal" | "bright"), color)) => {
+					let Some(index) = Palette::ANSI_NAMES.iter().position(|name| *name == color) else {
+						continue;
+					};
+					&mut colors.ansi[if group == "bright" { index + 8 } else { index }]
+				},
+				_ => continue,
+			};
+
+			*slot = Some(Self::parse_color(key, value)?);
+		}
+
+		Ok(())
+	}
+
+	// Just enough TOML for color configs: tables, dotted keys, quoted strings and one level of inline tables
+	fn parse_toml(contents: &str) -> Result<Vec<(String, String)>, ThemeFileError> {
+		let mut entries = Vec::new();
+		let mut table = String::new();
+
+		for (index, line) in contents.lines().enumerate() {
+			let line_number = index + 1;
+			let line = Self::strip_comment(line, '#').trim();
+			if line.is_empty() {
+				continue;
+			}
+
+			if line.starts_with('[') {
+				// [table] and [[array.of.tables]]
+				let Some(header) = line
+					.strip_prefix("[[")
+					.and_then(|line| line.strip_suffix("]]"))
+					.or_else(|| line.strip_prefix('[').and_then(|line| line.strip_suffix(']')))
+				else {
+					return Err(ThemeFileError::Syntax {
+						line: line_number,
+						message: format!("unterminated table header \"{line}\""),
+					});
+				};
+				table = Self::toml_key(header);
+				continue;
+			}
+
+			let Some((key, value)) = line.split_once('=') else {
+				return Err(ThemeFileError::Syntax {
+					line: line_number,
+					message: format!("expected key = value but found \"{line}\""),
+				});
+			};
+			let key = Self::join_key(&table, &Self::toml_key(key));
+			let value = value.trim();
+
+			if let Some(inline) = value.strip_prefix('{') {
+				let Some(inline) = inline.strip_suffix('}') else {
+					return Err(ThemeFileError::Syntax {
+						line: line_number,
+						message: String::from("unterminated inline table"),
+					});
+				};
+				for pair in inline.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+					let Some((inner_key, inner_value)) = pair.split_once('=') else {
+						return Err(ThemeFileError::Syntax {
+							line: line_number,
+							message: format!("expected key = value in inline table but found \"{pair}\""),
+						});
+					};
+					entries
+						.push((Self::join_key(&key, &Self::toml_key(inner_key)), Self::unquote(inner_value.trim(), line_number)?));
+				}
+			} else {
+				entries.push((key, Self::unquote(value, line_number)?));
+			}
+		}
+
+		Ok(entries)
+	}
+
+	fn toml_key(key: &str) -> String {
+		key.split('.').map(|part| part.trim().trim_matches(|c| c == '"' || c == '\'')).collect::<Vec<_>>().join(".")
+	}
+
+	// Just enough YAML for color configs: nested mappings by indentation with plain or quoted scalars
+	fn parse_yaml(contents: &str) -> Result<Vec<(String, String)>, ThemeFileError> {
+		let mut entries = Vec::new();
+		let mut parents: Vec<(usize, String)> = Vec::new();
+
+		for (index, raw_line) in contents.lines().enumerate() {
+			let line_number = index + 1;
+			let line = Self::strip_comment(raw_line, '#').trim_end();
+			if line.trim().is_empty() || line.trim() == "---" {
+				continue;
+			}
+			if line.starts_with('\t') {
+				return Err(ThemeFileError::Syntax {
+					line: line_number,
+					message: String::from("tabs are not allowed for indentation"),
+				});
+			}
+
+			let indent = line.len() - line.trim_start().len();
+			let line = line.trim_start();
+			if line.starts_with("- ") {
+				// Lists don't hold colors
+				continue;
+			}
+
+			let Some((key, value)) = line.split_once(':') else {
+				return Err(ThemeFileError::Syntax {
+					line: line_number,
+					message: format!("expected key: value but found \"{line}\""),
+				});
+			};
+
+			while parents.last().is_some_and(|(parent_indent, _)| *parent_indent >= indent) {
+				parents.pop();
+			}
+
+			let key = key.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+			let path = Self::join_key(parents.last().map_or("", |(_, path)| path), &key);
+			let value = value.trim();
+
+			if value.is_empty() {
+				parents.push((indent, path));
+			} else {
+				entries.push((path, Self::unquote(value, line_number)?));
+			}
+		}
+
+		Ok(entries)
+	}
+
+	fn parse_windows_terminal(
+		contents: &str,
+		scheme: Option<&str>,
+		colors: &mut ThemeColors,
+	) -> Result<(), ThemeFileError> {
+		const NAMES: [&str; 16] = [
+			"black",
+			"red",
+			"green",
+			"yellow",
+			"blue",
+			"purple",
+			"cyan",
+			"white",
+			"brightBlack",
+			"brightRed",
+			"brightGreen",
+			"brightYellow",
+			"brightBlue",
+			"brightPurple",
+			"brightCyan",
+			"brightWhite",
+		];
+
+		let json = Json::parse(contents)?;
+		// Either a whole settings.json with a "schemes" list or a single scheme
+		let schemes = match json.get("schemes") {
+			Some(Json::Array(schemes)) => schemes.iter().collect::<Vec<_>>(),
+			_ => vec![&json],
+		};
+
+		let found = match scheme {
+			Some(name) => schemes
+				.into_iter()
+				.find(
+					|scheme| matches!(scheme.get("name"), Some(Json::String(scheme_name)) if scheme_name.eq_ignore_ascii_case(name)),
+				)
+				.ok_or_else(|| ThemeFileError::SchemeNotFound(name.to_string()))?,
+			None => schemes.into_iter().next().ok_or(ThemeFileError::NoColors)?,
+		};
+
+		let color = |name: &str| -> Result<Option<Rgb>, ThemeFileError> {
+			match found.get(name) {
+				Some(Json::String(value)) => Self::parse_color(name, value).map(Some),
+				Some(_) => Err(ThemeFileError::InvalidColor {
+					name: name.to_string(),
+					value: String::from("not a string"),
+				}),
+				None => Ok(None),
+			}
+		};
+
+		for (index, name) in NAMES.iter().enumerate() {
+			colors.ansi[index] = color(name)?;
+		}
+		colors.foreground = color("foreground")?;
+		colors.background = color("background")?;
+
+		Ok(())
+	}
+
+	fn parse_kitty(contents: &str, colors: &mut ThemeColors) -> Result<(), ThemeFileError> {
+		for line in contents.lines() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+			let slot = match key {
+				"foreground" => &mut colors.foreground,
+				"background" => &mut colors.background,
+				_ => match key.strip_prefix("color").and_then(|index| index.parse::<usize>().ok()) {
+					Some(index) if index < 16 => &mut colors.ansi[index],
+					_ => continue,
+				},
+			};
+
+			*slot = Some(Self::parse_color(key, value)?);
+		}
+
+		Ok(())
+	}
+
+	fn parse_xresources(contents: &str, colors: &mut ThemeColors) -> Result<(), ThemeFileError> {
+		let mut defines: Vec<(String, String)> = Vec::new();
+
+		for (index, line) in contents.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('!') {
+				continue;
+			}
+
+			if let Some(define) = line.strip_prefix("#define") {
+				let mut parts = define.split_whitespace();
+				match (parts.next(), parts.next()) {
+					(Some(name), Some(value)) => defines.push((name.to_string(), value.to_string())),
+					_ => {
+						return Err(ThemeFileError::Syntax {
+							line: index + 1,
+							message: format!("expected #define NAME VALUE but found \"{line}\""),
+						});
+					},
+				}
+				continue;
+			}
+			if line.starts_with('#') {
+				// Other preprocessor directives like #include
+				continue;
+			}
+
+			let Some((resource, value)) = line.split_once(':') else {
+				return Err(ThemeFileError::Syntax {
+					line: index + 1,
+					message: format!("expected resource: value but found \"{line}\""),
+				});
+			};
+			// *.color0, *color0, URxvt.color0 and XTerm*foreground all name the same color
+			let name = resource.trim().rsplit(['.', '*']).next().unwrap_or_default();
+			let value = value.trim();
+			let value = defines.iter().rev().find(|(define, _)| define == value).map_or(value, |(_, value)| value.as_str());
+
+			let slot = match name {
+				"foreground" => &mut colors.foreground,
+				"background" => &mut colors.background,
+				_ => match name.strip_prefix("color").and_then(|index| index.parse::<usize>().ok()) {
+					Some(index) if index < 16 => &mut colors.ansi[index],
+					_ => continue,
+				},
+			};
+
+			*slot = Some(Self::parse_color(resource.trim(), value)?);
+		}
+
+		Ok(())
+	}
+
+	fn join_key(parent: &str, key: &str) -> String {
+		if parent.is_empty() {
+			key.to_string()
+		} else {
+			format!("{parent}.{key}")
+		}
+	}
+
+	// Removes a comment that starts outside of quotes
+	fn strip_comment(line: &str, marker: char) -> &str {
+		let mut quote = None;
+		for (index, c) in line.char_indices() {
+			match (quote, c) {
+				(None, '"' | '\'') => quote = Some(c),
+				(Some(open), _) if open == c => quote = None,
+				(None, _) if c == marker => return &line[..index],
+				_ => {},
+			}
+		}
+		line
+	}
+
+	fn unquote(value: &str, line: usize) -> Result<String, ThemeFileError> {
+		for quote in ['"', '\''] {
+			if let Some(rest) = value.strip_prefix(quote) {
+				return rest.strip_suffix(quote).map(String::from).ok_or_else(|| ThemeFileError::Syntax {
+					line,
+					message: format!("unterminated string {value}"),
+				});
+			}
+		}
+		Ok(value.to_string())
+	}
+}
+
+#[derive(Debug, Default)]
+struct ThemeColors {
+	ansi: [Option<Rgb>; 16],
+	foreground: Option<Rgb>,
+	background: Option<Rgb>,
+}
+
+impl ThemeColors {
+	// Colors a file doesn't define fall back to xterm
+	fn into_palette(self) -> Result<Palette, ThemeFileError> {
+		if self.ansi.iter().all(Option::is_none) && self.foreground.is_none() && self.background.is_none() {
+			return Err(ThemeFileError::NoColors);
+		}
+
+		let mut ansi = Palette::XTERM;
+		for (slot, color) in ansi.iter_mut().zip(self.ansi) {
+			if let Some(color) = color {
+				*slot = color;
+			}
+		}
+
+		let mut palette = Palette::from_ansi(&ansi);
+		palette.foreground = self.foreground;
+		palette.background = self.background;
+		Ok(palette)
+	}
+}
+
+/// The parts of XML property lists that theme files use
+#[derive(Debug, Clone, PartialEq)]
+enum PlistValue {
+	Dict(Vec<(String, PlistValue)>),
+	Text(String),
+}
+
+struct Plist<'a> {
+	input: &'a str,
+	position: usize,
+}
+
+impl<'a> Plist<'a> {
+	fn parse(input: &'a str) -> Result<PlistValue, ThemeFileError> {
+		let mut plist = Self { input, position: 0 };
+
+		loop {
+			match plist.next_tag()? {
+				Some(tag) if tag == "plist" || tag.starts_with("plist ") => continue,
+				Some(tag) => return plist.value(&tag),
+				None => return Err(plist.error("no value found")),
+			}
+		}
+	}
+
+	fn line(&self) -> usize {
+		self.input[..self.position].matches('\n').count() + 1
+	}
+
+	fn error(&self, message: &str) -> ThemeFileError {
+		ThemeFileError::Syntax {
+			line: self.line(),
+			message: message.to_string(),
+		}
+	}
+
+	// Returns the contents of the next element tag while skipping declarations and comments
+	fn next_tag(&mut self) -> Result<Option<String>, ThemeFileError> {
+		loop {
+			let Some(start) = self.input[self.position..].find('<') else {
+				return Ok(None);
+			};
+			self.position += start;
+			let rest = &self.input[self.position..];
+
+			let end_marker = if rest.starts_with("<!--") {
+				"-->"
+			} else if rest.starts_with("<?") {
+				"?>"
+			} else {
+				">"
+			};
+			let Some(end) = rest.find(end_marker) else {
+				return Err(self.error("unterminated tag"));
+			};
+			let tag = &rest[1..end];
+			self.position += end + end_marker.len();
+
+			if !tag.starts_with('!') && !tag.starts_with('?') {
+				return Ok(Some(tag.trim().to_string()));
+			}
+		}
+	}
+
+	fn text_until(&mut self, closing: &str) -> Result<String, ThemeFileError> {
+		let Some(end) = self.input[self.position..].find(closing) else {
+			return Err(self.error(&format!("missing {closing}")));
+		};
+		let text = &self.input[self.position..self.position + end];
+		self.position += end + closing.len();
+
+		Ok(
+			text
+				.replace("&lt;", "<")
+				.replace("&gt;", ">")
+				.replace("&quot;", "\"")
+				.replace("&apos;", "'")
+				.replace("&amp;", "&"),
+		)
+	}
+
+	fn value(&mut self, tag: &str) -> Result<PlistValue, ThemeFileError> {
+		match tag {
+			"dict" => {
+				let mut entries = Vec::new();
+				loop {
+					match self.next_tag()?.as_deref() {
+						Some("/dict") => return Ok(PlistValue::Dict(entries)),
+						Some("key") => {
+							let key = self.text_until("</key>")?;
+							let Some(value_tag) = self.next_tag()? else {
+								return Err(self.error(&format!("missing value for key \"{key}\"")));
+							};
+							entries.push((key, self.value(&value_tag)?));
+						},
+						Some(other) => return Err(self.error(&format!("expected <key> but found <{other}>"))),
+						None => return Err(self.error("missing </dict>")),
+					}
+				}
+			},
+			"array" => loop {
+				match self.next_tag()?.as_deref() {
+					Some("/array") => return Ok(PlistValue::Text(String::new())),
+					Some(tag) => {
+						self.value(tag)?;
+					},
+					None => return Err(self.error("missing </array>")),
+				}
+			},
+			"true/" | "false/" => Ok(PlistValue::Text(tag.trim_end_matches('/').to_string())),
+			"string" | "real" | "integer" | "data" | "date" => Ok(PlistValue::Text(self.text_until(&format!("</{tag}>"))?)),
+			other => Err(self.error(&format!("unexpected <{other}>"))),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const RED: Rgb = Rgb::new(0xff, 0x00, 0x00);
+	const FOREGROUND: Rgb = Rgb::new(0xee, 0xee, 0xee);
+	const BACKGROUND: Rgb = Rgb::new(0x11, 0x11, 0x11);
+
+	#[test]
+	fn from_path_test() {
+		assert_eq!(ThemeFormat::from_path(Path::new("Dracula.itermcolors")), Some(ThemeFormat::ITerm));
+		assert_eq!(ThemeFormat::from_path(Path::new("alacritty.TOML")), Some(ThemeFormat::AlacrittyToml));
+		assert_eq!(ThemeFormat::from_path(Path::new("alacritty.yml")), Some(ThemeFormat::AlacrittyYaml));
+		assert_eq!(ThemeFormat::from_path(Path::new("settings.json")), Some(ThemeFormat::WindowsTerminal));
+		assert_eq!(ThemeFormat::from_path(Path::new("kitty/theme.conf")), Some(ThemeFormat::Kitty));
+		assert_eq!(ThemeFormat::from_path(Path::new("/home/me/.Xresources")), Some(ThemeFormat::Xresources));
+		assert_eq!(ThemeFormat::from_path(Path::new("theme.txt")), None);
+	}
+
+	#[test]
+	fn load_errors_test() {
+		assert_eq!(
+			ThemeFormat::load(Path::new("theme.txt"), None),
+			Err(ThemeFileError::UnknownFormat(String::from("theme.txt")))
+		);
+		assert!(matches!(ThemeFormat::load(Path::new("/does/not/exist.toml"), None), Err(ThemeFileError::Io(_))));
+	}
+
+	#[test]
+	fn iterm_test() {
+		let palette = ThemeFormat::ITerm
+			.parse(
+				r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<!-- red -->
+	<key>Ansi 1 Color</key>
+	<dict>
+		<key>Alpha Component</key>
+		<real>1</real>
+		<key>Blue Component</key>
+		<real>0.0</real>
+		<key>Color Space</key>
+		<string>sRGB</string>
+		<key>Green Component</key>
+		<real>0.0</real>
+		<key>Red Component</key>
+		<real>1</real>
+	</dict>
+	<key>Background Color</key>
+	<dict>
+		<key>Blue Component</key>
+		<real>0.066666666666666666</real>
+		<key>Green Component</key>
+		<real>0.066666666666666666</real>
+		<key>Red Component</key>
+		<real>0.066666666666666666</real>
+	</dict>
+	<key>Use Bright Bold</key>
+	<true/>
+</dict>
+</plist>"#,
+				None,
+			)
+			.unwrap();
+
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.get(2), Palette::default().get(2));
+		assert_eq!(palette.background, Some(BACKGROUND));
+		assert_eq!(palette.foreground, None);
+	}
+
+	#[test]
+	fn iterm_errors_test() {
+		assert_eq!(
+			ThemeFormat::ITerm
+				.parse("<plist><dict>\n<key>Ansi 1 Color</key>\n<dict><key>Red Component</key><real>1</real>", None),
+			Err(ThemeFileError::Syntax {
+				line: 3,
+				message: String::from("missing </dict>"),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::ITerm.parse(
+				"<plist><dict><key>Ansi 1 Color</key><dict><key>Red Component</key><real>red</real><key>Green Component</key><real>0</real><key>Blue Component</key><real>0</real></dict></dict></plist>",
+				None
+			),
+			Err(ThemeFileError::InvalidColor {
+				name: String::from("Ansi 1 Color Red Component"),
+				value: String::from("red"),
+			})
+		);
+		assert_eq!(ThemeFormat::ITerm.parse("<plist><dict></dict></plist>", None), Err(ThemeFileError::NoColors));
+	}
+
+	#[test]
+	fn alacritty_toml_test() {
+		let palette = ThemeFormat::AlacrittyToml
+			.parse(
+				r##"
+# Colors (Tomorrow Night)
+[colors.primary]
+background = '#111111'
+foreground = "#eeeeee" # trailing comment
+
+[colors.normal]
+red = "0xff0000"
+
+[colors.bright]
+black = '#7f7f7f'
+
+[colors]
+dim = { red = "#880000" }
+
+[font]
+size = 12
+"##,
+				None,
+			)
+			.unwrap();
+
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.get(8), Rgb::new(0x7f, 0x7f, 0x7f));
+		assert_eq!(palette.foreground, Some(FOREGROUND));
+		assert_eq!(palette.background, Some(BACKGROUND));
+
+		// Inline tables
+		let palette =
+			ThemeFormat::AlacrittyToml.parse("[colors]\nnormal = { red = '#ff0000', green = '#00ff00' }", None).unwrap();
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.get(2), Rgb::new(0, 0xff, 0));
+	}
+
+	#[test]
+	fn alacritty_toml_errors_test() {
+		assert_eq!(
+			ThemeFormat::AlacrittyToml.parse("[colors.normal]\nred = '#ff0000\n", None),
+			Err(ThemeFileError::Syntax {
+				line: 2,
+				message: String::from("unterminated string '#ff0000"),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::AlacrittyToml.parse("[colors.normal\nred = '#ff0000'", None),
+			Err(ThemeFileError::Syntax {
+				line: 1,
+				message: String::from("unterminated table header \"[colors.normal\""),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::AlacrittyToml.parse("[colors.normal]\nred '#ff0000'", None),
+			Err(ThemeFileError::Syntax {
+				line: 2,
+				message: String::from("expected key = value but found \"red '#ff0000'\""),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::AlacrittyToml.parse("[colors.normal]\nred = 'crimson'", None),
+			Err(ThemeFileError::InvalidColor {
+				name: String::from("colors.normal.red"),
+				value: String::from("crimson"),
+			})
+		);
+		assert_eq!(ThemeFormat::AlacrittyToml.parse("[font]\nsize = 12", None), Err(ThemeFileError::NoColors));
+	}
+
+	#[test]
+	fn alacritty_yaml_test() {
+		let palette = ThemeFormat::AlacrittyYaml
+			.parse(
+				r##"
+# Colors (Tomorrow Night)
+font:
+  size: 12
+colors:
+  primary:
+    background: '0x111111'
+    foreground: "#eeeeee" # comment
+  normal:
+    black:   '#000000'
+    red:     '#ff0000'
+  bright:
+    white: '#ffffff'
+key_bindings:
+  - { key: V, mods: Control, action: Paste }
+"##,
+				None,
+			)
+			.unwrap();
+
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.get(15), Rgb::new(0xff, 0xff, 0xff));
+		assert_eq!(palette.foreground, Some(FOREGROUND));
+		assert_eq!(palette.background, Some(BACKGROUND));
+	}
+
+	#[test]
+	fn alacritty_yaml_errors_test() {
+		assert_eq!(
+			ThemeFormat::AlacrittyYaml.parse("colors:\n\tnormal:\n", None),
+			Err(ThemeFileError::Syntax {
+				line: 2,
+				message: String::from("tabs are not allowed for indentation"),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::AlacrittyYaml.parse("colors:\n  normal\n", None),
+			Err(ThemeFileError::Syntax {
+				line: 2,
+				message: String::from("expected key: value but found \"normal\""),
+			})
+		);
+	}
+
+	#[test]
+	fn windows_terminal_test() {
+		let settings = r##"
+// This file was initially generated by Windows Terminal
+{
+	"$schema": "https://aka.ms/terminal-profiles-schema",
+	"profiles": { "list": [ { "name": "PowerShell", "hidden": false } ] },
+	/* Color schemes */
+	"schemes": [
+		{
+			"name": "Campbell",
+			"foreground": "#CCCCCC",
+			"background": "#0C0C0C",
+			"red": "#C50F1F",
+		},
+		{
+			"name": "My \"Theme\"",
+			"foreground": "#EEEEEE",
+			"background": "#111111",
+			"red": "#FF0000",
+			"brightPurple": "#FF00FF"
+		},
+	],
+}
+"##;
+
+		let palette = ThemeFormat::WindowsTerminal.parse(settings, Some("my \"theme\"")).unwrap();
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.get(13), Rgb::new(0xff, 0, 0xff));
+		assert_eq!(palette.foreground, Some(FOREGROUND));
+		assert_eq!(palette.background, Some(BACKGROUND));
+
+		let palette = ThemeFormat::WindowsTerminal.parse(settings, None).unwrap();
+		assert_eq!(palette.get(1), Rgb::new(0xc5, 0x0f, 0x1f));
+
+		// A single scheme
+		let palette = ThemeFormat::WindowsTerminal.parse(r##"{ "name": "x", "red": "#ff0000" }"##, None).unwrap();
+		assert_eq!(palette.get(1), RED);
+	}
+
+	#[test]
+	fn windows_terminal_errors_test() {
+		assert_eq!(
+			ThemeFormat::WindowsTerminal.parse(r##"{ "schemes": [ { "name": "a", "red": "#ff0000" } ] }"##, Some("b")),
+			Err(ThemeFileError::SchemeNotFound(String::from("b")))
+		);
+		assert_eq!(
+			ThemeFormat::WindowsTerminal.parse("{\n\"red\": \"#ff0000\"\n\"green\": \"#00ff00\"\n}", None),
+			Err(ThemeFileError::Syntax {
+				line: 3,
+				message: String::from("expected \",\" or \"}\""),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::WindowsTerminal.parse(r#"{ "red": 12 }"#, None),
+			Err(ThemeFileError::InvalidColor {
+				name: String::from("red"),
+				value: String::from("not a string"),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::WindowsTerminal.parse("{ \"red\": \"#ff0000\" /* unterminated", None),
+			Err(ThemeFileError::Syntax {
+				line: 1,
+				message: String::from("unterminated comment"),
+			})
+		);
+	}
+
+	#[test]
+	fn kitty_test() {
+		let palette = ThemeFormat::Kitty
+			.parse(
+				"# vim:ft=kitty\nforeground   #eeeeee\nbackground #111111\n\n#: red\ncolor1 #ff0000\ncolor200 #000000\ncursor #ffffff\nfont_size 12\n",
+				None,
+			)
+			.unwrap();
+
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.foreground, Some(FOREGROUND));
+		assert_eq!(palette.background, Some(BACKGROUND));
+
+		assert_eq!(
+			ThemeFormat::Kitty.parse("color1 ff0000", None),
+			Err(ThemeFileError::InvalidColor {
+				name: String::from("color1"),
+				value: String::from("ff0000"),
+			})
+		);
+	}
+
+	#[test]
+	fn xresources_test() {
+		let palette = ThemeFormat::Xresources
+			.parse(
+				"! Base16\n#define base08 #ff0000\n#include \"other\"\n*.foreground: #eeeeee\nURxvt*background: rgb:11/11/11\n*color1: base08\nXTerm.vt100.color9: #ff0000\nURxvt.font: xft:Hack\n",
+				None,
+			)
+			.unwrap();
+
+		assert_eq!(palette.get(1), RED);
+		assert_eq!(palette.get(9), RED);
+		assert_eq!(palette.foreground, Some(FOREGROUND));
+		assert_eq!(palette.background, Some(BACKGROUND));
+	}
+
+	#[test]
+	fn xresources_errors_test() {
+		assert_eq!(
+			ThemeFormat::Xresources.parse("*color1 #ff0000", None),
+			Err(ThemeFileError::Syntax {
+				line: 1,
+				message: String::from("expected resource: value but found \"*color1 #ff0000\""),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::Xresources.parse("#define base08\n", None),
+			Err(ThemeFileError::Syntax {
+				line: 1,
+				message: String::from("expected #define NAME VALUE but found \"#define base08\""),
+			})
+		);
+		assert_eq!(
+			ThemeFormat::Xresources.parse("*color1: undefined_macro", None),
+			Err(ThemeFileError::InvalidColor {
+				name: String::from("*color1"),
+				value: String::from("undefined_macro"),
+			})
+		);
+	}
+}
@@ -0,0 +1,330 @@
+use crate::palette::{Palette, Rgb};
+
+/// A named set of the 16 ANSI colors and default colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+	pub name: &'static str,
+	pub label: &'static str,
+	pub ansi: [Rgb; 16],
+	pub foreground: Rgb,
+	pub background: Rgb,
+}
+
+impl Theme {
+	pub const XTERM: Theme = Theme {
+		name: "xterm",
+		label: "xterm",
+		ansi: Palette::XTERM,
+		foreground: Rgb::from_hex(0x000000),
+		background: Rgb::from_hex(0xffffff),
+	};
+
+	pub const VGA: Theme = Theme {
+		name: "vga",
+		label: "VGA",
+		ansi: [
+			Rgb::from_hex(0x000000),
+			Rgb::from_hex(0xaa0000),
+			Rgb::from_hex(0x00aa00),
+			Rgb::from_hex(0xaa5500),
+			Rgb::from_hex(0x0000aa),
+			Rgb::from_hex(0xaa00aa),
+			Rgb::from_hex(0x00aaaa),
+			Rgb::from_hex(0xaaaaaa),
+			Rgb::from_hex(0x555555),
+			Rgb::from_hex(0xff5555),
+			Rgb::from_hex(0x55ff55),
+			Rgb::from_hex(0xffff55),
+			Rgb::from_hex(0x5555ff),
+			Rgb::from_hex(0xff55ff),
+			Rgb::from_hex(0x55ffff),
+			Rgb::from_hex(0xffffff),
+		],
+		foreground: Rgb::from_hex(0xaaaaaa),
+		background: Rgb::from_hex(0x000000),
+	};
+
+	pub const SOLARIZED_DARK: Theme = Theme {
+		name: "solarized-dark",
+		label: "Solarized Dark",
+		ansi: [
+			Rgb::from_hex(0x073642),
+			Rgb::from_hex(0xdc322f),
+			Rgb::from_hex(0x859900),
+			Rgb::from_hex(0xb58900),
+			Rgb::from_hex(0x268bd2),
+			Rgb::from_hex(0xd33682),
+			Rgb::from_hex(0x2aa198),
+			Rgb::from_hex(0xeee8d5),
+			Rgb::from_hex(0x002b36),
+			Rgb::from_hex(0xcb4b16),
+			Rgb::from_hex(0x586e75),
+			Rgb::from_hex(0x657b83),
+			Rgb::from_hex(0x839496),
+			Rgb::from_hex(0x6c71c4),
+			Rgb::from_hex(0x93a1a1),
+			Rgb::from_hex(0xfdf6e3),
+		],
+		foreground: Rgb::from_hex(0x839496),
+		background: Rgb::from_hex(0x002b36),
+	};
+
+	pub const SOLARIZED_LIGHT: Theme = Theme {
+		name: "solarized-light",
+		label: "Solarized Light",
+		ansi: [
+			Rgb::from_hex(0x073642),
+			Rgb::from_hex(0xdc322f),
+			Rgb::from_hex(0x859900),
+			Rgb::from_hex(0xb58900),
+			Rgb::from_hex(0x268bd2),
+			Rgb::from_hex(0xd33682),
+			Rgb::from_hex(0x2aa198),
+			Rgb::from_hex(0xeee8d5),
+			Rgb::from_hex(0x002b36),
+			Rgb::from_hex(0xcb4b16),
+			Rgb::from_hex(0x586e75),
+			Rgb::from_hex(0x657b83),
+			Rgb::from_hex(0x839496),
+			Rgb::from_hex(0x6c71c4),
+			Rgb::from_hex(0x93a1a1),
+			Rgb::from_hex(0xfdf6e3),
+		],
+		foreground: Rgb::from_hex(0x657b83),
+		background: Rgb::from_hex(0xfdf6e3),
+	};
+
+	pub const DRACULA: Theme = Theme {
+		name: "dracula",
+		label: "Dracula",
+		ansi: [
+			Rgb::from_hex(0x21222c),
+			Rgb::from_hex(0xff5555),
+			Rgb::from_hex(0x50fa7b),
+			Rgb::from_hex(0xf1fa8c),
+			Rgb::from_hex(0xbd93f9),
+			Rgb::from_hex(0xff79c6),
+			Rgb::from_hex(0x8be9fd),
+			Rgb::from_hex(0xf8f8f2),
+			Rgb::from_hex(0x6272a4),
+			Rgb::from_hex(0xff6e6e),
+			Rgb::from_hex(0x69ff94),
+			Rgb::from_hex(0xffffa5),
+			Rgb::from_hex(0xd6acff),
+			Rgb::from_hex(0xff92df),
+			Rgb::from_hex(0xa4ffff),
+			Rgb::from_hex(0xffffff),
+		],
+		foreground: Rgb::from_hex(0xf8f8f2),
+		background: Rgb::from_hex(0x282a36),
+	};
+
+	pub const GRUVBOX: Theme = Theme {
+		name: "gruvbox",
+		label: "Gruvbox Dark",
+		ansi: [
+			Rgb::from_hex(0x282828),
+			Rgb::from_hex(0xcc241d),
+			Rgb::from_hex(0x98971a),
+			Rgb::from_hex(0xd79921),
+			Rgb::from_hex(0x458588),
+			Rgb::from_hex(0xb16286),
+			Rgb::from_hex(0x689d6a),
+			Rgb::from_hex(0xa89984),
+			Rgb::from_hex(0x928374),
+			Rgb::from_hex(0xfb4934),
+			Rgb::from_hex(0xb8bb26),
+			Rgb::from_hex(0xfabd2f),
+			Rgb::from_hex(0x83a598),
+			Rgb::from_hex(0xd3869b),
+			Rgb::from_hex(0x8ec07c),
+			Rgb::from_hex(0xebdbb2),
+		],
+		foreground: Rgb::from_hex(0xebdbb2),
+		background: Rgb::from_hex(0x282828),
+	};
+
+	pub const ONE_DARK: Theme = Theme {
+		name: "one-dark",
+		label: "One Dark",
+		ansi: [
+			Rgb::from_hex(0x282c34),
+			Rgb::from_hex(0xe06c75),
+			Rgb::from_hex(0x98c379),
+			Rgb::from_hex(0xe5c07b),
+			Rgb::from_hex(0x61afef),
+			Rgb::from_hex(0xc678dd),
+			Rgb::from_hex(0x56b6c2),
+			Rgb::from_hex(0xabb2bf),
+			Rgb::from_hex(0x5c6370),
+			Rgb::from_hex(0xe06c75),
+			Rgb::from_hex(0x98c379),
+			Rgb::from_hex(0xe5c07b),
+			Rgb::from_hex(0x61afef),
+			Rgb::from_hex(0xc678dd),
+			Rgb::from_hex(0x56b6c2),
+			Rgb::from_hex(0xffffff),
+		],
+		foreground: Rgb::from_hex(0xabb2bf),
+		background: Rgb::from_hex(0x282c34),
+	};
+
+	pub const NORD: Theme = Theme {
+		name: "nord",
+		label: "Nord",
+		ansi: [
+			Rgb::from_hex(0x3b4252),
+			Rgb::from_hex(0xbf616a),
+			Rgb::from_hex(0xa3be8c),
+			Rgb::from_hex(0xebcb8b),
+			Rgb::from_hex(0x81a1c1),
+			Rgb::from_hex(0xb48ead),
+			Rgb::from_hex(0x88c0d0),
+			Rgb::from_hex(0xe5e9f0),
+			Rgb::from_hex(0x4c566a),
+			Rgb::from_hex(0xbf616a),
+			Rgb::from_hex(0xa3be8c),
+			Rgb::from_hex(0xebcb8b),
+			Rgb::from_hex(0x81a1c1),
+			Rgb::from_hex(0xb48ead),
+			Rgb::from_hex(0x8fbcbb),
+			Rgb::from_hex(0xeceff4),
+		],
+		foreground: Rgb::from_hex(0xd8dee9),
+		background: Rgb::from_hex(0x2e3440),
+	};
+
+	pub const TANGO: Theme = Theme {
+		name: "tango",
+		label: "Tango",
+		ansi: [
+			Rgb::from_hex(0x2e3436),
+			Rgb::from_hex(0xcc0000),
+			Rgb::from_hex(0x4e9a06),
+			Rgb::from_hex(0xc4a000),
+			Rgb::from_hex(0x3465a4),
+			Rgb::from_hex(0x75507b),
+			Rgb::from_hex(0x06989a),
+			Rgb::from_hex(0xd3d7cf),
+			Rgb::from_hex(0x555753),
+			Rgb::from_hex(0xef2929),
+			Rgb::from_hex(0x8ae234),
+			Rgb::from_hex(0xfce94f),
+			Rgb::from_hex(0x729fcf),
+			Rgb::from_hex(0xad7fa8),
+			Rgb::from_hex(0x34e2e2),
+			Rgb::from_hex(0xeeeeec),
+		],
+		foreground: Rgb::from_hex(0xd3d7cf),
+		background: Rgb::from_hex(0x2e3436),
+	};
+
+	pub const TERMINAL_APP: Theme = Theme {
+		name: "terminal-app",
+		label: "macOS Terminal.app",
+		ansi: [
+			Rgb::from_hex(0x000000),
+			Rgb::from_hex(0x990000),
+			Rgb::from_hex(0x00a600),
+			Rgb::from_hex(0x999900),
+			Rgb::from_hex(0x0000b2),
+			Rgb::from_hex(0xb200b2),
+			Rgb::from_hex(0x00a6b2),
+			Rgb::from_hex(0xbfbfbf),
+			Rgb::from_hex(0x666666),
+			Rgb::from_hex(0xe50000),
+			Rgb::from_hex(0x00d900),
+			Rgb::from_hex(0xe5e500),
+			Rgb::from_hex(0x0000ff),
+			Rgb::from_hex(0xe500e5),
+			Rgb::from_hex(0x00e5e5),
+			Rgb::from_hex(0xe5e5e5),
+		],
+		foreground: Rgb::from_hex(0x000000),
+		background: Rgb::from_hex(0xffffff),
+	};
+
+	pub const CAMPBELL: Theme = Theme {
+		name: "campbell",
+		label: "Windows Terminal Campbell",
+		ansi: [
+			Rgb::from_hex(0x0c0c0c),
+			Rgb::from_hex(0xc50f1f),
+			Rgb::from_hex(0x13a10e),
+			Rgb::from_hex(0xc19c00),
+			Rgb::from_hex(0x0037da),
+			Rgb::from_hex(0x881798),
+			Rgb::from_hex(0x3a96dd),
+			Rgb::from_hex(0xcccccc),
+			Rgb::from_hex(0x767676),
+			Rgb::from_hex(0xe74856),
+			Rgb::from_hex(0x16c60c),
+			Rgb::from_hex(0xf9f1a5),
+			Rgb::from_hex(0x3b78ff),
+			Rgb::from_hex(0xb4009e),
+			Rgb::from_hex(0x61d6d6),
+			Rgb::from_hex(0xf2f2f2),
+		],
+		foreground: Rgb::from_hex(0xcccccc),
+		background: Rgb::from_hex(0x0c0c0c),
+	};
+
+	pub const ALL: [Theme; 11] = [
+		Self::XTERM,
+		Self::VGA,
+		Self::SOLARIZED_DARK,
+		Self::SOLARIZED_LIGHT,
+		Self::DRACULA,
+		Self::GRUVBOX,
+		Self::ONE_DARK,
+		Self::NORD,
+		Self::TANGO,
+		Self::TERMINAL_APP,
+		Self::CAMPBELL,
+	];
+
+	pub fn by_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|theme| theme.name.eq_ignore_ascii_case(name) || theme.label.eq_ignore_ascii_case(name))
+	}
+
+	pub fn palette(&self) -> Palette {
+		let mut palette = Palette::from_ansi(&self.ansi);
+		palette.foreground = Some(self.foreground);
+		palette.background = Some(self.background);
+		palette
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn by_name_test() {
+		assert_eq!(Theme::by_name("dracula"), Some(Theme::DRACULA));
+		assert_eq!(Theme::by_name("Solarized-Light"), Some(Theme::SOLARIZED_LIGHT));
+		assert_eq!(Theme::by_name("One Dark"), Some(Theme::ONE_DARK));
+		assert_eq!(Theme::by_name("windows terminal campbell"), Some(Theme::CAMPBELL));
+		assert_eq!(Theme::by_name("nope"), None);
+	}
+
+	#[test]
+	fn unique_names_test() {
+		for (index, theme) in Theme::ALL.iter().enumerate() {
+			assert!(Theme::ALL[index + 1..].iter().all(|other| other.name != theme.name));
+			assert_eq!(Theme::by_name(theme.name), Some(*theme));
+		}
+	}
+
+	#[test]
+	fn palette_test() {
+		let palette = Theme::NORD.palette();
+		assert_eq!(palette.get(1), Rgb::new(0xbf, 0x61, 0x6a));
+		assert_eq!(palette.get(15), Rgb::new(0xec, 0xef, 0xf4));
+		assert_eq!(palette.get(196), Palette::default().get(196));
+		assert_eq!(palette.foreground, Some(Rgb::new(0xd8, 0xde, 0xe9)));
+		assert_eq!(palette.background, Some(Rgb::new(0x2e, 0x34, 0x40)));
+
+		assert_eq!(Theme::XTERM.palette().get(1), Palette::default().get(1));
+	}
+}